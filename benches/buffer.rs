@@ -43,7 +43,7 @@ fn push_pop_crossbeam(t: usize) {
 
         for _ in 0..black_box(ELEMENTS / 20) {
             for _ in 0..20 {
-                writer.try_send(PAYLOAD);
+                let _ = writer.try_send(PAYLOAD);
             }
             std::thread::yield_now();
         }
@@ -81,7 +81,7 @@ fn push_pop_lockfree(t: usize) {
 
         for _ in 0..black_box(ELEMENTS / 20) {
             for _ in 0..20 {
-                writer.send(PAYLOAD);
+                let _ = writer.send(PAYLOAD);
             }
             std::thread::yield_now();
         }
@@ -174,7 +174,7 @@ fn crossbeam_ping(t: usize) {
             });
         }
 
-        writer.try_send(PAYLOAD);
+        let _ = writer.try_send(PAYLOAD);
     });
 }
 
@@ -198,12 +198,12 @@ fn lockfree_ping(t: usize) {
             });
         }
 
-        writer.send(PAYLOAD);
+        let _ = writer.send(PAYLOAD);
     });
 }
 
 fn bench(c: &mut Criterion) {
-    let mut group = c.benchmark_group(format!("Bench Throughput With Variable Threads"));
+    let mut group = c.benchmark_group("Bench Throughput With Variable Threads".to_string());
     THREADS.into_iter().for_each(|t| {
         group.bench_with_input(
             BenchmarkId::from_parameter(format!("Sling {t} Thread(s)")),
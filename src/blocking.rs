@@ -0,0 +1,302 @@
+//! Parking reader threads until the writer publishes, rather than busy-polling the queue.
+
+use alloc::sync::Arc;
+use core::ops::Deref;
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+use std::task::Wake;
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+use crate::{AtomicUsize, LockError, NoUninit, Reader, RingBuffer, WriteGuard};
+
+/// Unparks the thread it belongs to when woken.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+std::thread_local! {
+    static WAKER: Waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+}
+
+impl<T: NoUninit, const N: usize> RingBuffer<T, N> {
+    /// Pops the next message of the reader whose progress is tracked by `index`, `version` and
+    /// `missed`, parking the current thread until the writer publishes should the queue be
+    /// empty. Gives up once `deadline` has passed.
+    pub(crate) fn pop_blocking(
+        &self,
+        index: &AtomicUsize,
+        version: &AtomicUsize,
+        missed: &AtomicUsize,
+        deadline: Option<Instant>,
+    ) -> Option<T> {
+        WAKER.with(|waker| {
+            let mut cx = Context::from_waker(waker);
+
+            loop {
+                if let Poll::Ready(val) = self.poll_pop(index, version, missed, &mut cx) {
+                    return Some(val);
+                }
+
+                if !park(deadline) {
+                    return None;
+                }
+            }
+        })
+    }
+
+    /// Takes the writer's lock, parking the current thread until the current [`WriteGuard`] is
+    /// dropped should another thread hold it. Gives up once `deadline` has passed.
+    fn lock_blocking(&self, deadline: Option<Instant>) -> Option<WriteGuard<'_, T, N>> {
+        WAKER.with(|waker| loop {
+            if let Ok(writer) = self.try_lock() {
+                return Some(writer);
+            }
+
+            // We need to try once more after registering, as the lock may have been released
+            // just before the writer could see our waker.
+            self.writers.register(waker);
+
+            if let Ok(writer) = self.try_lock() {
+                return Some(writer);
+            }
+
+            if !park(deadline) {
+                return None;
+            }
+        })
+    }
+
+    /// Acquires the [`RingBuffer`]'s [`WriteGuard`], parking the current thread until the
+    /// current [`WriteGuard`] is dropped should another thread hold it.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let writer = buffer.try_lock().unwrap();
+    ///
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| buffer.lock().push_back(1));
+    ///
+    ///     drop(writer);
+    /// });
+    ///
+    /// assert_eq!(buffer.latest(), Some(1));
+    /// ```
+    pub fn lock(&self) -> WriteGuard<'_, T, N> {
+        // Without a deadline we never give up.
+        self.lock_blocking(None).unwrap()
+    }
+
+    /// Tries to acquire the [`RingBuffer`]'s [`WriteGuard`], parking the current thread for up
+    /// to `timeout` should another thread hold it. Fails if the lock was not released in time.
+    /// ```rust
+    /// # use sling::*;
+    /// # use std::time::Duration;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let writer = buffer.try_lock().unwrap();
+    ///
+    /// assert!(buffer.try_lock_for(Duration::from_millis(10)).is_err());
+    /// ```
+    pub fn try_lock_for(&self, timeout: Duration) -> Result<WriteGuard<'_, T, N>, LockError> {
+        // A timeout too long to represent is as good as none.
+        self.lock_blocking(Instant::now().checked_add(timeout))
+            .ok_or_else(|| self.lock_error())
+    }
+}
+
+/// Parks the current thread until it is unparked or `deadline` passes, returning `false` once
+/// the deadline has passed. Parking may return spuriously, so callers simply check again.
+fn park(deadline: Option<Instant>) -> bool {
+    match deadline {
+        None => thread::park(),
+        Some(deadline) => {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            thread::park_timeout(deadline - now);
+        }
+    }
+
+    true
+}
+
+impl<T: NoUninit, B, const N: usize> Reader<T, B>
+where
+    B: Deref<Target = RingBuffer<T, N>>,
+{
+    /// Pops the next element from the front, parking the current thread until the writer
+    /// publishes should the queue be empty. Just like [`Reader::pop_front`], this skips
+    /// ahead should the writer have overrun us.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| assert_eq!(reader.pop_front_blocking(), 7));
+    ///
+    ///     writer.push_back(7);
+    /// });
+    /// ```
+    pub fn pop_front_blocking(&self) -> T {
+        // Without a deadline we never give up.
+        self.buffer
+            .pop_blocking(&self.index, &self.version, &self.missed, None)
+            .unwrap()
+    }
+
+    /// Pops the next element from the front, parking the current thread for up to `timeout`
+    /// until the writer publishes should the queue be empty. Returns `None` if nothing was
+    /// published in time.
+    /// ```rust
+    /// # use sling::*;
+    /// # use std::time::Duration;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let reader = buffer.reader();
+    ///
+    /// assert_eq!(reader.pop_front_timeout(Duration::from_millis(10)), None);
+    /// ```
+    pub fn pop_front_timeout(&self, timeout: Duration) -> Option<T> {
+        // A timeout too long to represent is as good as none.
+        let deadline = Instant::now().checked_add(timeout);
+        self.buffer
+            .pop_blocking(&self.index, &self.version, &self.missed, deadline)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_pop_blocking() {
+        let buffer = RingBuffer::<_, 64>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        std::thread::scope(|s| {
+            let reader = &reader;
+            let handles: Vec<_> = (0..4)
+                .map(|_| s.spawn(move || (0..8).map(|_| reader.pop_front_blocking()).sum::<u32>()))
+                .collect();
+
+            for i in 0..32 {
+                writer.push_back(i);
+                std::thread::sleep(Duration::from_micros(50));
+            }
+
+            let sum: u32 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+            assert_eq!(sum, (0..32).sum::<u32>());
+        });
+    }
+
+    #[test]
+    fn test_pop_timeout() {
+        let buffer = RingBuffer::<_, 64>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        let start = Instant::now();
+        assert_eq!(reader.pop_front_timeout(Duration::from_millis(20)), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(10));
+                writer.push_back(1);
+            });
+
+            assert_eq!(reader.pop_front_timeout(Duration::from_secs(10)), Some(1));
+        });
+
+        writer.push_back(2);
+        assert_eq!(reader.pop_front_timeout(Duration::MAX), Some(2));
+    }
+
+    #[test]
+    fn test_pop_blocking_missed() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        writer.push_slice(&[0, 1, 2, 3, 4, 5]);
+
+        assert_eq!(reader.pop_front_blocking(), 4);
+        assert_eq!(reader.missed(), 4);
+
+        writer.push_slice(&[6, 7, 8, 9, 10, 11, 12]);
+
+        assert_eq!(reader.pop_front_timeout(Duration::from_secs(10)), Some(9));
+        assert_eq!(reader.missed(), 8);
+    }
+
+    #[test]
+    fn test_lock_handover() {
+        let buffer = RingBuffer::<_, 64>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        writer.push_back(0);
+
+        std::thread::scope(|s| {
+            let buffer = &buffer;
+            let handles: Vec<_> = (1..5)
+                .map(|i| {
+                    s.spawn(move || {
+                        buffer.lock().push_back(i);
+                    })
+                })
+                .collect();
+
+            std::thread::sleep(Duration::from_millis(10));
+            drop(writer);
+
+            handles.into_iter().for_each(|h| h.join().unwrap());
+        });
+
+        let mut read: Vec<_> = core::iter::from_fn(|| reader.pop_front()).collect();
+        read.sort();
+        assert_eq!(read, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_lock_timeout() {
+        let buffer = RingBuffer::<u32, 64>::new();
+
+        let writer = buffer.try_lock().unwrap();
+
+        let start = Instant::now();
+        assert!(buffer.try_lock_for(Duration::from_millis(20)).is_err());
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                std::thread::sleep(Duration::from_millis(10));
+                drop(writer);
+            });
+
+            assert!(buffer.try_lock_for(Duration::from_secs(10)).is_ok());
+        });
+
+        assert!(buffer.try_lock_for(Duration::MAX).is_ok());
+    }
+}
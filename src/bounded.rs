@@ -0,0 +1,370 @@
+//! Letting the writer hold back rather than overrun registered readers, which turns a
+//! [`RingBuffer`] into a bounded, lossless queue.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt::{Debug, Display};
+
+use crate::{
+    precedes, spin_loop, AtomicBool, AtomicUsize, NoUninit, Ordering, PopError, RingBuffer,
+    Subscriber, WriteGuard, Writer,
+};
+
+impl<T: NoUninit, const N: usize> RingBuffer<T, N> {
+    /// Creates a new [`RegisteredReader`]. Like a [`Subscriber`], it reads every message
+    /// published after its creation, but it also lets the writer know how far it has read, so
+    /// that [`WriteGuard::try_push_back`] never overwrites a message it has not read yet.
+    ///
+    /// Messages published while the reader registers may still be overrun.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<[u8; 16], 1024> = RingBuffer::new();
+    ///
+    /// let mut reader = buffer.register();
+    /// ```
+    pub fn register(&self) -> RegisteredReader<'_, T, N> {
+        let subscriber = self.subscriber();
+
+        // The subscriber starts out at the first block of the writer's next lap.
+        let cursor = Arc::new(Cursor::new(subscriber.next()));
+
+        self.readers.register(Arc::clone(&cursor));
+
+        RegisteredReader {
+            buffer: self,
+            subscriber,
+            cursor,
+        }
+    }
+}
+
+impl<'write, T: NoUninit, const N: usize> WriteGuard<'write, T, N> {
+    /// Pushes a new value to the back of the queue, unless that would overwrite a message some
+    /// [`RegisteredReader`] has not read yet, in which case the value is handed back. Returns
+    /// the sequence number of the message otherwise.
+    ///
+    /// Without registered readers this never fails. Note that [`WriteGuard::push_back`] keeps
+    /// overrunning registered readers, too.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 2> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let mut reader = buffer.register();
+    ///
+    /// assert_eq!(writer.try_push_back(1), Ok(0));
+    /// assert_eq!(writer.try_push_back(2), Ok(1));
+    /// assert_eq!(writer.try_push_back(3), Err(Full(3)));
+    ///
+    /// assert_eq!(reader.pop_front(), Some(1));
+    /// assert_eq!(writer.try_push_back(3), Ok(2));
+    /// ```
+    pub fn try_push_back(&mut self, val: T) -> Result<u64, Full<T>> {
+        // We are the only writer, so the next block and its sequence cannot change under us.
+        let index = self.buffer.index.load(Ordering::Relaxed);
+        let seq = self.buffer.data[index].seq.load(Ordering::Relaxed);
+
+        if !self.buffer.readers.admits(seq, index) {
+            return Err(Full(val));
+        }
+
+        Ok(self.buffer.push(val))
+    }
+}
+
+/// Returned by [`WriteGuard::try_push_back`] when pushing would overwrite a message a
+/// [`RegisteredReader`] has not read yet. It hands back the value that could not be pushed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Full<T>(pub T);
+
+impl<T> Display for Full<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("the queue is full")
+    }
+}
+
+impl<T: Debug> core::error::Error for Full<T> {}
+
+/// Exclusive read access to a [`RingBuffer`], which holds back [`WriteGuard::try_push_back`]
+/// until it has read the messages about to be overwritten. It otherwise behaves just like a
+/// [`Subscriber`], and unregisters itself once dropped.
+#[derive(Debug)]
+pub struct RegisteredReader<'read, T: NoUninit, const N: usize> {
+    buffer: &'read RingBuffer<T, N>,
+    subscriber: Subscriber<'read, T, N>,
+    /// The position of the next message we are going to read.
+    cursor: Arc<Cursor>,
+}
+
+unsafe impl<'read, T: NoUninit, const N: usize> Send for RegisteredReader<'read, T, N> {}
+
+impl<'read, T: NoUninit, const N: usize> RegisteredReader<'read, T, N> {
+    /// Pops the next element from the front. See [`Subscriber::pop_front`].
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.pop_front_with_seq().map(|(_, val)| val)
+    }
+
+    /// Pops the next element from the front alongside its sequence number. See
+    /// [`SharedReader::pop_front_with_seq`](crate::SharedReader::pop_front_with_seq).
+    pub fn pop_front_with_seq(&mut self) -> Option<(u64, T)> {
+        let message = self.subscriber.pop_front_with_seq();
+        self.advance();
+        message
+    }
+
+    /// Tries to pop the next element from the front, reporting why no element could be popped
+    /// on failure. This only fails with [`PopError::Lagged`] should the writer have overrun us
+    /// through [`WriteGuard::push_back`], or while we registered.
+    pub fn try_pop_front(&mut self) -> Result<T, PopError> {
+        let res = self.subscriber.try_pop();
+        self.advance();
+        res.map(|(_, val)| val)
+    }
+
+    /// Lets the writer know how far we have read, including the messages we skipped.
+    #[inline]
+    fn advance(&self) {
+        self.cursor.store(self.subscriber.next());
+    }
+}
+
+impl<'read, T: NoUninit, const N: usize> Drop for RegisteredReader<'read, T, N> {
+    fn drop(&mut self) {
+        self.buffer.readers.unregister(&self.cursor);
+    }
+}
+
+/// The position of the next message a registered reader is going to read, made up of the
+/// sequence its block is going to have and the block's index. Sequences wrap around, so
+/// positions are only ever compared through `before`.
+#[derive(Debug)]
+struct Cursor {
+    seq: AtomicUsize,
+    index: AtomicUsize,
+}
+
+impl Cursor {
+    fn new((seq, index): (usize, usize)) -> Cursor {
+        Cursor {
+            seq: AtomicUsize::new(seq),
+            index: AtomicUsize::new(index),
+        }
+    }
+
+    /// Moves the cursor to `next`. The index is stored first, so that the writer never sees the
+    /// cursor ahead of where it really is, see `Cursor::load`. This is `Release`, so that our
+    /// copy of the message is ordered before the writer overwrites it.
+    #[inline]
+    fn store(&self, (seq, index): (usize, usize)) {
+        self.index.store(index, Ordering::Relaxed);
+        self.seq.store(seq, Ordering::Release);
+    }
+
+    /// Loads the cursor, which may lag behind concurrent updates, but never runs ahead of them.
+    /// This is `Acquire` to pair with `Cursor::store`.
+    #[inline]
+    fn load(&self) -> (usize, usize) {
+        let seq = self.seq.load(Ordering::Acquire);
+        (seq, self.index.load(Ordering::Relaxed))
+    }
+}
+
+/// Whether the message in the block at `index` with sequence `seq` comes before the position
+/// `next`.
+#[inline]
+fn before((seq, index): (usize, usize), next: (usize, usize)) -> bool {
+    precedes(seq, next.0) || (seq == next.0 && index < next.1)
+}
+
+/// The cursors of the readers registered with a [`RingBuffer`]. The writer only looks at them
+/// once it has used up the room it found the last time.
+pub(crate) struct Registry {
+    locked: AtomicBool,
+    /// Set once a reader registers, as the writer then needs to look at the cursors again.
+    stale: AtomicBool,
+    /// The writer may overwrite every message before this position without looking at the
+    /// cursors, or any message should it be `None`. Only the writer ever accesses it.
+    limit: UnsafeCell<Option<(usize, usize)>>,
+    cursors: UnsafeCell<Vec<Arc<Cursor>>>,
+}
+
+unsafe impl Send for Registry {}
+unsafe impl Sync for Registry {}
+
+impl Registry {
+    #[cfg(not(loom))]
+    pub(crate) const fn new() -> Registry {
+        Registry {
+            locked: AtomicBool::new(false),
+            stale: AtomicBool::new(false),
+            limit: UnsafeCell::new(None),
+            cursors: UnsafeCell::new(Vec::new()),
+        }
+    }
+
+    #[cfg(loom)]
+    pub(crate) fn new() -> Registry {
+        Registry {
+            locked: AtomicBool::new(false),
+            stale: AtomicBool::new(false),
+            limit: UnsafeCell::new(None),
+            cursors: UnsafeCell::new(Vec::new()),
+        }
+    }
+
+    fn register(&self, cursor: Arc<Cursor>) {
+        self.with_cursors(|cursors| {
+            cursors.push(cursor);
+            self.stale.store(true, Ordering::Relaxed);
+        });
+    }
+
+    fn unregister(&self, cursor: &Arc<Cursor>) {
+        self.with_cursors(|cursors| cursors.retain(|c| !Arc::ptr_eq(c, cursor)));
+    }
+
+    /// Whether the writer may overwrite the message in the block at `index` with sequence `seq`,
+    /// as every registered reader has read it already.
+    #[inline]
+    fn admits(&self, seq: usize, index: usize) -> bool {
+        // # Safety: Only the writer accesses the limit, and there only ever is one.
+        let limit = unsafe { &mut *self.limit.get() };
+
+        if !self.stale.load(Ordering::Relaxed)
+            && limit.is_none_or(|limit| before((seq, index), limit))
+        {
+            return true;
+        }
+
+        *limit = self.rescan();
+        limit.is_none_or(|limit| before((seq, index), limit))
+    }
+
+    /// Looks at the cursors again, returning the earliest position a reader is going to read
+    /// next, if any.
+    fn rescan(&self) -> Option<(usize, usize)> {
+        self.with_cursors(|cursors| {
+            self.stale.store(false, Ordering::Relaxed);
+
+            cursors
+                .iter()
+                .map(|c| c.load())
+                .reduce(|a, b| if before(b, a) { b } else { a })
+        })
+    }
+
+    /// Runs `f` with exclusive access to the registered cursors.
+    fn with_cursors<R>(&self, f: impl FnOnce(&mut Vec<Arc<Cursor>>) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+
+        // # Safety: We are holding the lock.
+        let res = f(unsafe { &mut *self.cursors.get() });
+
+        self.locked.store(false, Ordering::Release);
+
+        res
+    }
+}
+
+impl Debug for Registry {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Registry")
+            .field("stale", &self.stale.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate std;
+
+    #[test]
+    fn test_backpressure() {
+        let buffer = RingBuffer::<u32, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let mut reader = buffer.register();
+
+        assert_eq!(writer.try_push_back(0), Ok(0));
+        writer.extend(1..4);
+        assert_eq!(writer.try_push_back(4), Err(Full(4)));
+
+        assert_eq!(reader.pop_front(), Some(0));
+        assert_eq!(reader.pop_front(), Some(1));
+        assert_eq!(writer.try_push_back(4), Ok(4));
+        assert_eq!(writer.try_push_back(5), Ok(5));
+        assert_eq!(writer.try_push_back(6), Err(Full(6)));
+
+        // Once the reader is gone, nothing holds the writer back anymore.
+        drop(reader);
+        assert_eq!(writer.try_push_back(6), Ok(6));
+        assert_eq!(writer.try_push_back(7), Ok(7));
+    }
+
+    #[test]
+    fn test_backpressure_wraparound() {
+        let buffer = RingBuffer::<u32, 4>::new();
+
+        // The blocks and the version start out just short of wrapping around, rather than at 0.
+        for block in &buffer.data {
+            block.seq.store(usize::MAX - 3, Ordering::Relaxed);
+        }
+        buffer.version.store(usize::MAX - 3, Ordering::Relaxed);
+
+        let mut writer = buffer.try_lock().unwrap();
+        let mut reader = buffer.register();
+
+        // The sequences wrap around in the second lap, which must not let the writer through.
+        for i in 0..5 {
+            for j in i * 4..i * 4 + 4 {
+                assert!(writer.try_push_back(j).is_ok());
+            }
+            assert_eq!(writer.try_push_back(0), Err(Full(0)));
+
+            for j in i * 4..i * 4 + 4 {
+                assert_eq!(reader.pop_front(), Some(j));
+            }
+            assert_eq!(reader.pop_front(), None);
+        }
+    }
+
+    #[test]
+    fn test_backpressure_lossless() {
+        let buffer = RingBuffer::<u64, 8>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let readers: std::vec::Vec<_> = (0..3).map(|_| buffer.register()).collect();
+
+        std::thread::scope(|s| {
+            for mut reader in readers {
+                s.spawn(move || {
+                    for i in 0..10_000 {
+                        loop {
+                            match reader.try_pop_front() {
+                                Ok(val) => break assert_eq!(val, i),
+                                Err(PopError::Empty) => std::thread::yield_now(),
+                                Err(err) => panic!("{err}"),
+                            }
+                        }
+                    }
+                });
+            }
+
+            for i in 0..10_000 {
+                let mut val = i;
+                while let Err(Full(v)) = writer.try_push_back(val) {
+                    val = v;
+                    std::thread::yield_now();
+                }
+            }
+        });
+    }
+}
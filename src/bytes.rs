@@ -0,0 +1,560 @@
+//! A ring of variable-length byte records, for payloads that do not fit a fixed-size `Copy` type.
+
+use core::cell::UnsafeCell;
+use core::fmt::{Debug, Display};
+use core::mem::size_of;
+
+#[cfg(not(loom))]
+use core::sync::atomic::fence;
+#[cfg(loom)]
+use loom::sync::atomic::fence;
+
+use crate::copy::{Grid, Region};
+use crate::{advance, spin_loop, AtomicBool, AtomicUsize, LockError, Ordering, Padded};
+
+/// The length prefix in front of every record.
+const HEADER: usize = size_of::<usize>();
+
+/// A fixed-size, non-write-blocking ring of `N` bytes, that carries length-prefixed records of
+/// arbitrary size and can be safely shared across threads. Records wrap around the end of the
+/// ring, so no space is lost to padding.
+///
+/// Positions in the ring only ever grow, until they wrap around at the largest multiple of `N`
+/// that fits a `usize`. The writer announces how far it is about to write through `intent`
+/// before touching any bytes, and publishes the record through `head` afterwards. A reader
+/// copies a record and then checks that `intent` has not reached it yet, which is the same retry
+/// discipline the blocks of a [`RingBuffer`](crate::RingBuffer) follow.
+pub struct ByteRing<const N: usize> {
+    locked: Padded<AtomicBool>,
+    intent: Padded<AtomicUsize>,
+    head: Padded<AtomicUsize>,
+    // Twice the number of records published, and odd while `head` is being moved.
+    count: Padded<AtomicUsize>,
+    // The number of records pushed so far, which unlike `count` never wraps around. Only the
+    // writer holding the lock touches it.
+    pushed: UnsafeCell<u64>,
+    data: UnsafeCell<[u8; N]>,
+}
+
+unsafe impl<const N: usize> Send for ByteRing<N> {}
+unsafe impl<const N: usize> Sync for ByteRing<N> {}
+
+impl<const N: usize> Default for ByteRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ByteRing<N> {
+    /// Fails the build should `N` not hold more than a record's length prefix.
+    const HOLDS_HEADER: () = assert!(
+        N > HEADER,
+        "a ByteRing must hold more than a record's length prefix"
+    );
+
+    /// Constructs a new, empty ring of `N` bytes. Being `const`, it lets large rings live in a
+    /// `static` rather than on the stack.
+    ///
+    /// Rings too small to hold a record's length prefix fail to compile.
+    /// ```rust
+    /// # use sling::*;
+    /// static RING: ByteRing<4096> = ByteRing::new();
+    /// ```
+    /// ```rust,compile_fail
+    /// # use sling::*;
+    /// let ring: ByteRing<8> = ByteRing::new();
+    /// ```
+    #[cfg(not(loom))]
+    pub const fn new() -> ByteRing<N> {
+        let () = Self::HOLDS_HEADER;
+
+        ByteRing {
+            locked: Padded(AtomicBool::new(false)),
+            intent: Padded(AtomicUsize::new(0)),
+            head: Padded(AtomicUsize::new(0)),
+            count: Padded(AtomicUsize::new(0)),
+            pushed: UnsafeCell::new(0),
+            data: UnsafeCell::new([0; N]),
+        }
+    }
+
+    /// Loom's atomics cannot be constructed in a `const` context.
+    #[cfg(loom)]
+    pub fn new() -> ByteRing<N> {
+        let () = Self::HOLDS_HEADER;
+
+        ByteRing {
+            locked: Padded(AtomicBool::new(false)),
+            intent: Padded(AtomicUsize::new(0)),
+            head: Padded(AtomicUsize::new(0)),
+            count: Padded(AtomicUsize::new(0)),
+            pushed: UnsafeCell::new(0),
+            data: UnsafeCell::new([0; N]),
+        }
+    }
+
+    /// Returns the largest record that fits into the ring.
+    #[inline]
+    pub const fn max_record(&self) -> usize {
+        N - HEADER
+    }
+
+    /// Tries to acquire the [`ByteRing`]'s [`ByteWriteGuard`]. As there can only ever be one
+    /// thread holding a [`ByteWriteGuard`], this fails if another thread is already holding the
+    /// lock.
+    /// ```rust
+    /// # use sling::*;
+    /// let ring: ByteRing<4096> = ByteRing::new();
+    ///
+    /// let Ok(mut writer) = ring.try_lock() else { return };
+    /// ```
+    #[inline]
+    pub fn try_lock(&self) -> Result<ByteWriteGuard<'_, N>, LockError> {
+        if !self.locked.swap(true, Ordering::Acquire) {
+            Ok(ByteWriteGuard { buffer: self })
+        } else {
+            Err(LockError::new())
+        }
+    }
+
+    /// Creates a new [`ByteReader`], which reads every record published after its creation.
+    /// Just like a [`Subscriber`](crate::Subscriber), it does not share its progress.
+    /// ```rust
+    /// # use sling::*;
+    /// let ring: ByteRing<4096> = ByteRing::new();
+    ///
+    /// let mut reader = ring.reader();
+    /// ```
+    #[inline]
+    pub fn reader(&self) -> ByteReader<'_, N> {
+        let (position, count) = self.snapshot();
+
+        ByteReader {
+            buffer: self,
+            position,
+            count,
+        }
+    }
+
+    /// Returns the position of `head` alongside twice the number of records published before
+    /// it, see `count`.
+    fn snapshot(&self) -> (usize, usize) {
+        loop {
+            let count = self.count.load(Ordering::Acquire);
+            if count & 1 == 1 {
+                spin_loop();
+                continue;
+            }
+
+            // This is `Acquire` so that the following load of `count` cannot happen before it.
+            let head = self.head.load(Ordering::Acquire);
+
+            if count == self.count.load(Ordering::Relaxed) {
+                return (head, count);
+            }
+        }
+    }
+
+    /// Checks whether the writer may have overwritten the bytes starting at `position` so far.
+    #[inline]
+    fn intact(&self, position: usize) -> bool {
+        // Make sure the bytes we copied are read before we check `intent`.
+        fence(Ordering::Acquire);
+
+        Self::distance(position, self.intent.load(Ordering::Relaxed)) <= N
+    }
+
+    /// Moves `position` ahead by `count` bytes. Positions wrap around at a multiple of `N`
+    /// rather than at `usize::MAX`, so that `position % N` keeps going through the ring in order.
+    #[inline]
+    fn forward(position: usize, count: usize) -> usize {
+        advance(position, count, N)
+    }
+
+    /// Returns how many bytes `to` is ahead of `from`, should the positions have wrapped around
+    /// in between, see [`ByteRing::forward`].
+    #[inline]
+    fn distance(from: usize, to: usize) -> usize {
+        match to.checked_sub(from) {
+            Some(distance) => distance,
+            None => to + (usize::MAX - usize::MAX % N - from),
+        }
+    }
+
+    /// The memory of the ring, which is only ever accessed through it.
+    #[inline]
+    fn region(&self) -> Region {
+        // # Safety: The ring is only ever accessed through its region.
+        unsafe { Region::new(self.data.get().cast(), N) }
+    }
+
+    /// Copies `src` into the ring at `position`, wrapping around its end.
+    #[inline]
+    fn copy_in(&self, position: usize, src: &[u8]) {
+        let start = position % N;
+        let first = src.len().min(N - start);
+
+        // # Safety: Both parts stay within the ring, and we are the only writer.
+        unsafe {
+            let region = self.region();
+            region.store(start, &src[..first]);
+            region.store(0, &src[first..]);
+        }
+    }
+
+    /// Copies the bytes at `position` into `dst`, wrapping around the ring's end. The copy may be
+    /// torn should the writer overwrite them concurrently, which readers detect through
+    /// [`ByteRing::intact`].
+    #[inline]
+    fn copy_out(&self, position: usize, dst: &mut [u8]) {
+        let start = position % N;
+        let first = dst.len().min(N - start);
+
+        // # Safety: Both parts stay within the ring. Torn reads are discarded by the caller.
+        unsafe {
+            let region = self.region();
+            region.load(start, dst.as_mut_ptr(), first);
+            region.load(0, dst.as_mut_ptr().add(first), dst.len() - first);
+        }
+    }
+}
+
+impl<const N: usize> Debug for ByteRing<N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ByteRing")
+            .field("locked", &self.locked)
+            .field("intent", &self.intent)
+            .field("head", &self.head)
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
+/// Read access to a [`ByteRing`]. Every [`ByteReader`] reads every record on its own, copying it
+/// into a buffer provided by the caller.
+///
+/// Clones continue from the same position, but do not share progress with the original
+/// [`ByteReader`].
+#[derive(Clone)]
+pub struct ByteReader<'read, const N: usize> {
+    buffer: &'read ByteRing<N>,
+    position: usize,
+    /// Twice the number of records read or skipped so far, just like the ring's `count`.
+    count: usize,
+}
+
+unsafe impl<'read, const N: usize> Send for ByteReader<'read, N> {}
+
+impl<'read, const N: usize> ByteReader<'read, N> {
+    /// Pops the next record from the front into `buf`, returning its length. Should the writer
+    /// have overrun us, the reader skips ahead to the latest record. A `buf` of
+    /// [`ByteRing::max_record`] bytes fits every record.
+    ///
+    /// Fails with [`BytePopError::TooLarge`] if the record does not fit into `buf`, leaving it to
+    /// be popped into a larger one.
+    /// ```rust
+    /// # use sling::*;
+    /// let ring: ByteRing<64> = ByteRing::new();
+    ///
+    /// let mut writer = ring.try_lock().unwrap();
+    /// let mut reader = ring.reader();
+    ///
+    /// writer.push_back(b"hello");
+    /// writer.push_back(b"");
+    ///
+    /// let mut buf = [0; 8];
+    /// assert_eq!(reader.pop_into(&mut buf[..4]), Err(BytePopError::TooLarge { len: 5 }));
+    /// assert_eq!(reader.pop_into(&mut buf), Ok(5));
+    /// assert_eq!(&buf[..5], b"hello");
+    /// assert_eq!(reader.pop_into(&mut buf), Ok(0));
+    /// assert_eq!(reader.pop_into(&mut buf), Err(BytePopError::Empty));
+    /// ```
+    pub fn pop_into(&mut self, buf: &mut [u8]) -> Result<usize, BytePopError> {
+        loop {
+            match self.try_pop_into(buf) {
+                Err(BytePopError::Lagged { .. }) => continue,
+                res => return res,
+            }
+        }
+    }
+
+    /// Tries to pop the next record from the front into `buf`, returning its length. When
+    /// lagging behind, the reader skips ahead to the latest record and reports how many records
+    /// it missed, rather than popping one.
+    pub fn try_pop_into(&mut self, buf: &mut [u8]) -> Result<usize, BytePopError> {
+        // This is `Acquire` to ensure we see the record's bytes.
+        if self.position == self.buffer.head.load(Ordering::Acquire) {
+            return Err(BytePopError::Empty);
+        }
+
+        let mut header = [0; HEADER];
+        self.buffer.copy_out(self.position, &mut header);
+
+        // We must not trust the length should the writer have overwritten it.
+        if !self.buffer.intact(self.position) {
+            return Err(self.resync());
+        }
+
+        let len = usize::from_ne_bytes(header);
+        let Some(dst) = buf.get_mut(..len) else {
+            return Err(BytePopError::TooLarge { len });
+        };
+
+        self.buffer
+            .copy_out(ByteRing::<N>::forward(self.position, HEADER), dst);
+
+        if !self.buffer.intact(self.position) {
+            return Err(self.resync());
+        }
+
+        self.position = ByteRing::<N>::forward(self.position, HEADER + len);
+        self.count = self.count.wrapping_add(2);
+
+        Ok(len)
+    }
+
+    /// Skips ahead to the writer's `head`, as we cannot find the start of the next record
+    /// anywhere else once it has been overwritten.
+    fn resync(&mut self) -> BytePopError {
+        let (position, count) = self.buffer.snapshot();
+
+        // The counts may have wrapped around in the meantime.
+        let missed = count.wrapping_sub(self.count) / 2;
+        self.position = position;
+        self.count = count;
+
+        BytePopError::Lagged { missed }
+    }
+}
+
+impl<'read, const N: usize> Debug for ByteReader<'read, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ByteReader")
+            .field("buffer", &self.buffer)
+            .field("position", &self.position)
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
+/// The reasons why [`ByteReader::try_pop_into`] did not pop a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BytePopError {
+    /// There are no new records in the ring.
+    Empty,
+    /// The writer has overrun the reader, and `missed` records were skipped. The reader has been
+    /// moved ahead to the writer's most recent record.
+    Lagged {
+        /// The number of records that were skipped.
+        missed: usize,
+    },
+    /// The next record does not fit into the buffer it was asked to be popped into. The record
+    /// has not been popped.
+    TooLarge {
+        /// The length of the record.
+        len: usize,
+    },
+}
+
+impl Display for BytePopError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BytePopError::Empty => f.write_str("the ring is empty"),
+            BytePopError::Lagged { missed } => f.write_fmt(format_args!(
+                "the reader lagged behind and missed {missed} records"
+            )),
+            BytePopError::TooLarge { len } => f.write_fmt(format_args!(
+                "the next record of {len} bytes does not fit into the buffer"
+            )),
+        }
+    }
+}
+
+impl core::error::Error for BytePopError {}
+
+/// Provides exclusive write access to the [`ByteRing`].
+#[derive(Debug)]
+pub struct ByteWriteGuard<'write, const N: usize> {
+    buffer: &'write ByteRing<N>,
+}
+
+unsafe impl<'write, const N: usize> Send for ByteWriteGuard<'write, N> {}
+
+impl<'write, const N: usize> ByteWriteGuard<'write, N> {
+    /// Pushes a new record to the back of the ring. This operation does not block. Returns the
+    /// sequence number of the record, which starts at 0 and is incremented by 1 for every
+    /// record pushed to the [`ByteRing`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the record is larger than [`ByteRing::max_record`].
+    /// ```rust
+    /// # use sling::*;
+    /// let ring: ByteRing<4096> = ByteRing::new();
+    ///
+    /// if let Ok(mut writer) = ring.try_lock() {
+    ///     assert_eq!(writer.push_back(&[12, 21, 04]), 0);
+    ///     assert_eq!(writer.push_back(b"sling"), 1);
+    /// };
+    /// ```
+    pub fn push_back(&mut self, record: &[u8]) -> u64 {
+        assert!(
+            record.len() <= self.buffer.max_record(),
+            "the record does not fit into the ByteRing"
+        );
+
+        let buffer = self.buffer;
+
+        // We are the only writer, so these cannot change under us.
+        let start = buffer.head.load(Ordering::Relaxed);
+        let count = buffer.count.load(Ordering::Relaxed);
+        let end = ByteRing::<N>::forward(start, HEADER + record.len());
+
+        // Announce the bytes we are about to overwrite before touching any of them.
+        buffer.intent.store(end, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        buffer.copy_in(start, &record.len().to_ne_bytes());
+        buffer.copy_in(ByteRing::<N>::forward(start, HEADER), record);
+
+        buffer.count.store(count.wrapping_add(1), Ordering::Relaxed);
+        buffer.head.store(end, Ordering::Release);
+        buffer.count.store(count.wrapping_add(2), Ordering::Release);
+
+        // # Safety: We are holding the lock, so nobody else touches the counter.
+        let pushed = unsafe { &mut *buffer.pushed.get() };
+        *pushed += 1;
+        *pushed - 1
+    }
+}
+
+impl<'write, const N: usize> Drop for ByteWriteGuard<'write, N> {
+    fn drop(&mut self) {
+        self.buffer.locked.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_byte_wrap() {
+        let ring = ByteRing::<32>::new();
+
+        let mut writer = ring.try_lock().unwrap();
+        let mut reader = ring.reader();
+
+        assert!(ring.try_lock().is_err());
+
+        // Every record takes up 8 + 5 bytes, so they soon start to wrap around the end.
+        let mut buf = [0; 5];
+        for i in 0..10u8 {
+            writer.push_back(&[i; 5]);
+            assert_eq!(reader.try_pop_into(&mut buf), Ok(5));
+            assert_eq!(buf, [i; 5]);
+        }
+
+        assert_eq!(reader.try_pop_into(&mut buf), Err(BytePopError::Empty));
+    }
+
+    #[test]
+    fn test_byte_lagged() {
+        let ring = ByteRing::<32>::new();
+
+        let mut writer = ring.try_lock().unwrap();
+        let mut reader = ring.reader();
+
+        for i in 0..4u8 {
+            writer.push_back(&[i; 8]);
+        }
+
+        let mut buf = [0; 8];
+        assert_eq!(
+            reader.try_pop_into(&mut buf),
+            Err(BytePopError::Lagged { missed: 4 })
+        );
+        assert_eq!(reader.try_pop_into(&mut buf), Err(BytePopError::Empty));
+
+        writer.push_back(b"next");
+        assert_eq!(reader.pop_into(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"next");
+    }
+
+    #[test]
+    fn test_byte_wraparound() {
+        let ring = ByteRing::<32>::new();
+
+        // The positions and the count start out just short of wrapping around, rather than at 0.
+        let end = usize::MAX - usize::MAX % 32;
+        ring.intent.store(end - 20, Ordering::Relaxed);
+        ring.head.store(end - 20, Ordering::Relaxed);
+        ring.count.store(usize::MAX - 3, Ordering::Relaxed);
+        let first = (usize::MAX / 2 - 1) as u64;
+        unsafe { *ring.pushed.get() = first };
+
+        let mut writer = ring.try_lock().unwrap();
+        let mut reader = ring.reader();
+
+        // Every record takes up 8 + 5 bytes, so the positions wrap around in the second one.
+        // Sequence numbers keep counting up regardless.
+        let mut buf = [0; 8];
+        for (i, seq) in (0..4u8).zip(first..) {
+            assert_eq!(writer.push_back(&[i; 5]), seq);
+            assert_eq!(reader.try_pop_into(&mut buf), Ok(5));
+            assert_eq!(buf[..5], [i; 5]);
+        }
+
+        // Overruns are still noticed, and counted across the wraparound.
+        for i in 0..4u8 {
+            writer.push_back(&[i; 8]);
+        }
+        assert_eq!(
+            reader.try_pop_into(&mut buf),
+            Err(BytePopError::Lagged { missed: 4 })
+        );
+
+        writer.push_back(b"next");
+        assert_eq!(reader.try_pop_into(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"next");
+    }
+
+    #[test]
+    fn test_byte_multi_reader() {
+        let ring = ByteRing::<1024>::new();
+
+        let mut writer = ring.try_lock().unwrap();
+        let reader = ring.reader();
+
+        std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let mut reader = reader.clone();
+                    s.spawn(move || {
+                        let mut read = 0;
+                        let mut buf = [0; 64];
+                        while read < 1000 {
+                            match reader.try_pop_into(&mut buf) {
+                                Ok(len) => {
+                                    // Every record repeats its length, so torn ones stand out.
+                                    assert!(buf[..len].iter().all(|&b| b as usize == len));
+                                    read += 1;
+                                }
+                                Err(BytePopError::Lagged { missed }) => read += missed,
+                                Err(_) => {}
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            for i in 0..1000usize {
+                writer.push_back(&[(i % 64) as u8; 64][..i % 64]);
+            }
+
+            handles.into_iter().for_each(|h| h.join().unwrap());
+        });
+    }
+}
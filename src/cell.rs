@@ -0,0 +1,157 @@
+//! A single seqlocked value, for publishing snapshots rather than a queue of messages.
+
+use crate::{
+    spin_loop, AtomicBool, AtomicUsize, Block, LockError, Message, NoUninit, Ordering, Padded,
+};
+
+/// A single, non-write-blocking value that can be safely shared across threads. It is guarded
+/// by the same seqlock as every block of a [`RingBuffer`](crate::RingBuffer): the writer never
+/// waits for readers, while readers retry should they observe a store in progress.
+///
+/// This suits values that are replaced as a whole, such as configuration or price snapshots.
+#[derive(Debug)]
+pub struct SeqLockCell<T: NoUninit> {
+    locked: Padded<AtomicBool>,
+    block: Padded<Block<T>>,
+}
+
+unsafe impl<T: NoUninit> Send for SeqLockCell<T> {}
+unsafe impl<T: NoUninit> Sync for SeqLockCell<T> {}
+
+impl<T: NoUninit> SeqLockCell<T> {
+    /// Constructs a new cell holding `val`.
+    /// ```rust
+    /// # use sling::*;
+    /// let cell = SeqLockCell::new([0u8; 16]);
+    /// ```
+    pub fn new(val: T) -> SeqLockCell<T> {
+        SeqLockCell {
+            locked: Padded(AtomicBool::new(false)),
+            // The cell starts out as if `val` had already been stored once.
+            block: Padded(Block {
+                seq: AtomicUsize::new(2),
+                laps: Message::new(1),
+                message: Message::new(val),
+            }),
+        }
+    }
+
+    /// Tries to acquire the [`SeqLockCell`]'s [`SeqLockGuard`]. As there can only ever be one
+    /// thread holding a [`SeqLockGuard`], this fails if another thread is already holding the
+    /// lock.
+    /// ```rust
+    /// # use sling::*;
+    /// let cell = SeqLockCell::new(0u64);
+    ///
+    /// let Ok(mut writer) = cell.try_lock() else { return };
+    /// ```
+    #[inline]
+    pub fn try_lock(&self) -> Result<SeqLockGuard<'_, T>, LockError> {
+        if !self.locked.swap(true, Ordering::Acquire) {
+            Ok(SeqLockGuard { cell: self })
+        } else {
+            Err(LockError::new())
+        }
+    }
+
+    /// Loads the current value, retrying for as long as the writer is storing a new one.
+    /// ```rust
+    /// # use sling::*;
+    /// let cell = SeqLockCell::new(1u64);
+    ///
+    /// cell.try_lock().unwrap().store(2);
+    ///
+    /// assert_eq!(cell.load(), 2);
+    /// ```
+    pub fn load(&self) -> T {
+        loop {
+            match self.try_load() {
+                Some(val) => return val,
+                None => spin_loop(),
+            }
+        }
+    }
+
+    /// Tries to load the current value, returning `None` should the writer be storing a new one
+    /// concurrently.
+    pub fn try_load(&self) -> Option<T> {
+        let seq = self.block.seq.load(Ordering::Acquire);
+
+        if seq & 1 == 1 {
+            return None;
+        }
+
+        self.block.read(seq).map(|(_, val)| val)
+    }
+}
+
+/// Provides exclusive write access to the [`SeqLockCell`].
+#[derive(Debug)]
+pub struct SeqLockGuard<'write, T: NoUninit> {
+    cell: &'write SeqLockCell<T>,
+}
+
+unsafe impl<'write, T: NoUninit> Send for SeqLockGuard<'write, T> {}
+
+impl<'write, T: NoUninit> SeqLockGuard<'write, T> {
+    /// Replaces the value of the cell. This operation does not block.
+    #[inline]
+    pub fn store(&mut self, val: T) {
+        let block = &self.cell.block;
+
+        block.start_write();
+        block.write(val);
+        block.end_write();
+    }
+}
+
+impl<'write, T: NoUninit> Drop for SeqLockGuard<'write, T> {
+    fn drop(&mut self) {
+        self.cell.locked.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate std;
+
+    #[test]
+    fn test_cell() {
+        let cell = SeqLockCell::new(1u32);
+
+        let mut writer = cell.try_lock().unwrap();
+        assert!(cell.try_lock().is_err());
+
+        assert_eq!(cell.try_load(), Some(1));
+
+        writer.store(2);
+        assert_eq!(cell.load(), 2);
+
+        drop(writer);
+        assert!(cell.try_lock().is_ok());
+    }
+
+    #[test]
+    fn test_cell_torn() {
+        let cell = SeqLockCell::new([0usize; 32]);
+
+        let mut writer = cell.try_lock().unwrap();
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..1000 {
+                        let val = cell.load();
+                        // Every store writes the same value to each element.
+                        assert!(val.iter().all(|&v| v == val[0]));
+                    }
+                });
+            }
+
+            for i in 0..1000 {
+                writer.store([i; 32]);
+            }
+        });
+    }
+}
@@ -0,0 +1,482 @@
+//! Copying messages in and out of shared memory with relaxed atomic loads and stores.
+//!
+//! Readers of a seqlock race with the writer by design, and discard whatever they read while
+//! the writer was busy. With plain (or volatile) copies such a race is undefined behavior, even
+//! if the result is thrown away. Copying word by word with atomics keeps the race well defined,
+//! while the fences around the copies order it with the sequence of the block.
+//!
+//! Racing atomic accesses must either be disjoint or have the same size, so both sides split the
+//! shared memory into the same grid of cells, which depends on nothing but its address, see
+//! [`Region`]. Every access covers a whole cell, however few of its bytes are being copied.
+
+#[cfg(not(loom))]
+use core::cell::UnsafeCell;
+#[cfg(loom)]
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of, MaybeUninit};
+use core::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+
+/// Types whose bytes are all initialized, so that they can be copied in and out of a buffer as
+/// plain words. Every message type has to implement it.
+///
+/// Readers race with the writer by design, so messages are copied with atomic loads and stores
+/// of whole words and bytes, see [`RingBuffer`](crate::RingBuffer). Loading padding bytes as
+/// integers is undefined behavior, so messages must not have any.
+///
+/// It is implemented for the primitive types and arrays of them. Tuples and most structs
+/// contain padding, but a `#[repr(C)]` struct whose fields leave no gaps may implement it.
+///
+/// # Safety
+///
+/// The type must not contain any uninitialized bytes, such as padding, the unused bytes of an
+/// enum or union, or a [`MaybeUninit`].
+/// ```rust
+/// # use sling::*;
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Quote {
+///     price: u64,
+///     size: u32,
+///     venue: [u8; 4],
+/// }
+///
+/// unsafe impl NoUninit for Quote {}
+///
+/// let buffer: RingBuffer<Quote, 16> = RingBuffer::new();
+/// ```
+/// Types with padding cannot be sent.
+/// ```rust,compile_fail
+/// # use sling::*;
+/// let buffer: RingBuffer<(u8, u32), 16> = RingBuffer::new();
+/// ```
+pub unsafe trait NoUninit: Copy {}
+
+macro_rules! no_uninit {
+    ($($t:ty),*) => {
+        $(unsafe impl NoUninit for $t {})*
+    };
+}
+
+no_uninit!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    ()
+);
+
+unsafe impl<T: NoUninit, const N: usize> NoUninit for [T; N] {}
+
+/// Returns the bytes of `val`, which are all initialized.
+#[inline]
+pub(crate) fn bytes_of<T: NoUninit>(val: &T) -> &[u8] {
+    slice_bytes(core::slice::from_ref(val))
+}
+
+/// Returns the bytes of `vals`, which are all initialized.
+#[inline]
+pub(crate) fn slice_bytes<T: NoUninit>(vals: &[T]) -> &[u8] {
+    // # Safety: `NoUninit` rules out uninitialized bytes, and the bytes stay borrowed.
+    unsafe { core::slice::from_raw_parts(vals.as_ptr().cast(), core::mem::size_of_val(vals)) }
+}
+
+/// The message of a block, which is only ever copied in and out with atomics. Zeroed bytes are
+/// a valid, if meaningless, message.
+#[cfg(not(loom))]
+#[repr(transparent)]
+pub(crate) struct Message<T>(UnsafeCell<MaybeUninit<T>>);
+
+#[cfg(not(loom))]
+impl<T: NoUninit> Message<T> {
+    #[inline]
+    pub(crate) const fn new(val: T) -> Message<T> {
+        Message(UnsafeCell::new(MaybeUninit::new(val)))
+    }
+
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub(crate) const fn zeroed() -> Message<T> {
+        Message(UnsafeCell::new(MaybeUninit::zeroed()))
+    }
+
+    /// The memory of the message, which is only ever accessed through it.
+    #[inline]
+    fn region(&self) -> Region {
+        // # Safety: The message is only ever accessed through its region.
+        unsafe { Region::new(self.0.get().cast(), size_of::<T>()) }
+    }
+}
+
+/// Loom cannot observe atomics we create from plain memory, so here the message is spread over
+/// loom's own atomics instead, in the same grid as the memory of a real message. The copies
+/// themselves are the same, which lets loom check every interleaving of them.
+#[cfg(loom)]
+pub(crate) struct Message<T> {
+    region: LoomRegion,
+    _marker: PhantomData<T>,
+}
+
+#[cfg(loom)]
+impl<T: NoUninit> Message<T> {
+    pub(crate) fn new(val: T) -> Message<T> {
+        let message = Message::zeroed();
+        message.store(val);
+        message
+    }
+
+    pub(crate) fn zeroed() -> Message<T> {
+        Message {
+            region: LoomRegion::new(size_of::<T>()),
+            _marker: PhantomData,
+        }
+    }
+
+    fn region(&self) -> &LoomRegion {
+        &self.region
+    }
+}
+
+impl<T: NoUninit> Message<T> {
+    /// Copies the message, which may be torn should the writer store to it concurrently.
+    #[inline]
+    pub(crate) fn load(&self) -> MaybeUninit<T> {
+        let mut dst = MaybeUninit::<T>::uninit();
+
+        // # Safety: `dst` has room for the whole message.
+        unsafe {
+            self.region()
+                .load(0, dst.as_mut_ptr().cast(), size_of::<T>())
+        };
+
+        dst
+    }
+
+    /// Replaces the message. Readers copying it concurrently see a torn message.
+    #[inline]
+    pub(crate) fn store(&self, val: T) {
+        // # Safety: `val` is a whole message.
+        unsafe { self.region().store(0, bytes_of(&val)) };
+    }
+
+    /// Replaces the bytes of the message starting at `offset` with `bytes`. Readers copying it
+    /// concurrently see a torn message.
+    ///
+    /// # Safety
+    ///
+    /// The `bytes` must fit into the message at `offset`.
+    #[inline]
+    pub(crate) unsafe fn store_at(&self, offset: usize, bytes: &[u8]) {
+        self.region().store(offset, bytes);
+    }
+}
+
+/// Shared memory that is only ever accessed through relaxed atomics. It is split into a fixed
+/// grid of cells: the aligned words it covers completely, and single bytes at its unaligned
+/// edges. Copies load and store whole cells, even if they only need some of their bytes, so
+/// that the sizes of racing accesses always agree.
+///
+/// Bytes within a word are updated by loading and storing the whole word, so only one thread
+/// may ever store to the memory at a time.
+pub(crate) trait Grid {
+    /// The length of the memory in bytes.
+    fn len(&self) -> usize;
+
+    /// The number of bytes in front of the first aligned word.
+    fn head(&self) -> usize;
+
+    /// Loads the cell at `cell`, which holds `size` bytes.
+    ///
+    /// # Safety
+    ///
+    /// The cell must be one of the grid.
+    unsafe fn load_cell(&self, cell: usize, size: usize) -> [u8; size_of::<usize>()];
+
+    /// Stores the first `size` of `bytes` to the cell at `cell`.
+    ///
+    /// # Safety
+    ///
+    /// The cell must be one of the grid.
+    unsafe fn store_cell(&self, cell: usize, size: usize, bytes: [u8; size_of::<usize>()]);
+
+    /// Returns the cell holding the byte at `at`, as the offset and length of the cell.
+    #[inline]
+    fn cell(&self, at: usize) -> (usize, usize) {
+        let head = self.head();
+        let body = head + (self.len() - head) / size_of::<usize>() * size_of::<usize>();
+
+        if at < head || at >= body {
+            (at, 1)
+        } else {
+            (at - (at - head) % size_of::<usize>(), size_of::<usize>())
+        }
+    }
+
+    /// Copies `len` bytes of the memory starting at `offset` into the local `dst`.
+    ///
+    /// # Safety
+    ///
+    /// `offset + len` must not exceed the memory, and `dst` must be valid for writes of `len`
+    /// bytes.
+    #[inline]
+    unsafe fn load(&self, offset: usize, dst: *mut u8, len: usize) {
+        let end = offset + len;
+        let mut at = offset;
+
+        while at < end {
+            let (cell, size) = self.cell(at);
+            let bytes = self.load_cell(cell, size);
+
+            // We only need the bytes of the cell that we are copying.
+            let (from, to) = (at - cell, size.min(end - cell));
+            core::ptr::copy_nonoverlapping(
+                bytes[from..to].as_ptr(),
+                dst.add(at - offset),
+                to - from,
+            );
+
+            at = cell + to;
+        }
+    }
+
+    /// Copies `src` into the memory, starting at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// `offset + src.len()` must not exceed the memory, and no other thread may store to it
+    /// concurrently.
+    #[inline]
+    unsafe fn store(&self, offset: usize, src: &[u8]) {
+        let end = offset + src.len();
+        let mut at = offset;
+
+        while at < end {
+            let (cell, size) = self.cell(at);
+            let (from, to) = (at - cell, size.min(end - cell));
+
+            // We are the only writer, so the bytes we keep cannot change in between.
+            let mut bytes = if to - from == size {
+                [0; size_of::<usize>()]
+            } else {
+                self.load_cell(cell, size)
+            };
+            bytes[from..to].copy_from_slice(&src[at - offset..cell + to - offset]);
+            self.store_cell(cell, size, bytes);
+
+            at = cell + to;
+        }
+    }
+}
+
+/// Plain memory, which we access through atomics created in place.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Region {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Region {
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads and writes of `len` bytes for as long as the region is
+    /// used, and the memory must not be accessed other than through a [`Grid`].
+    #[inline]
+    pub(crate) unsafe fn new(ptr: *mut u8, len: usize) -> Region {
+        Region { ptr, len }
+    }
+}
+
+impl Grid for Region {
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn head(&self) -> usize {
+        self.ptr.align_offset(align_of::<usize>()).min(self.len)
+    }
+
+    #[inline]
+    unsafe fn load_cell(&self, cell: usize, size: usize) -> [u8; size_of::<usize>()] {
+        let shared = self.ptr.add(cell);
+        let mut bytes = [0; size_of::<usize>()];
+
+        if size == 1 {
+            bytes[0] = AtomicU8::from_ptr(shared).load(Ordering::Relaxed);
+        } else {
+            let word = AtomicUsize::from_ptr(shared.cast()).load(Ordering::Relaxed);
+            bytes = word.to_ne_bytes();
+        }
+
+        bytes
+    }
+
+    #[inline]
+    unsafe fn store_cell(&self, cell: usize, size: usize, bytes: [u8; size_of::<usize>()]) {
+        let shared = self.ptr.add(cell);
+
+        if size == 1 {
+            AtomicU8::from_ptr(shared).store(bytes[0], Ordering::Relaxed);
+        } else {
+            let word = usize::from_ne_bytes(bytes);
+            AtomicUsize::from_ptr(shared.cast()).store(word, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The memory of a message under loom, made up of loom's atomics. A block's message follows its
+/// sequence, so it starts out aligned, and only its last bytes fall outside of whole words.
+#[cfg(loom)]
+pub(crate) struct LoomRegion {
+    len: usize,
+    words: alloc::vec::Vec<loom::sync::atomic::AtomicUsize>,
+    bytes: alloc::vec::Vec<loom::sync::atomic::AtomicU8>,
+}
+
+#[cfg(loom)]
+impl LoomRegion {
+    fn new(len: usize) -> LoomRegion {
+        LoomRegion {
+            len,
+            words: (0..len / size_of::<usize>())
+                .map(|_| loom::sync::atomic::AtomicUsize::new(0))
+                .collect(),
+            bytes: (0..len % size_of::<usize>())
+                .map(|_| loom::sync::atomic::AtomicU8::new(0))
+                .collect(),
+        }
+    }
+
+    /// The index of the byte at `cell` past the whole words.
+    fn byte(&self, cell: usize) -> usize {
+        cell - self.words.len() * size_of::<usize>()
+    }
+}
+
+#[cfg(loom)]
+impl Grid for LoomRegion {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn head(&self) -> usize {
+        0
+    }
+
+    unsafe fn load_cell(&self, cell: usize, size: usize) -> [u8; size_of::<usize>()] {
+        let mut bytes = [0; size_of::<usize>()];
+
+        if size == 1 {
+            bytes[0] = self.bytes[self.byte(cell)].load(Ordering::Relaxed);
+        } else {
+            bytes = self.words[cell / size_of::<usize>()]
+                .load(Ordering::Relaxed)
+                .to_ne_bytes();
+        }
+
+        bytes
+    }
+
+    unsafe fn store_cell(&self, cell: usize, size: usize, bytes: [u8; size_of::<usize>()]) {
+        if size == 1 {
+            self.bytes[self.byte(cell)].store(bytes[0], Ordering::Relaxed);
+        } else {
+            self.words[cell / size_of::<usize>()]
+                .store(usize::from_ne_bytes(bytes), Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_copy_unaligned() {
+        let mut shared = [0usize; 8];
+        let mut local = [0u8; 59];
+
+        // Start at an odd address, so the region has bytes at both of its edges.
+        let region = unsafe { Region::new(shared.as_mut_ptr().cast::<u8>().add(3), local.len()) };
+
+        // Records of odd lengths at odd offsets only cover parts of words, and are patched over
+        // by records covering whole words.
+        let records = [
+            (0, 5),
+            (3, 13),
+            (5, 1),
+            (13, 21),
+            (5, 16),
+            (29, 30),
+            (2, 57),
+            (53, 6),
+        ];
+
+        for (i, (offset, len)) in records.into_iter().enumerate() {
+            let record: [u8; 64] = core::array::from_fn(|j| (i * 64 + j) as u8);
+            let record = &record[..len];
+
+            local[offset..offset + len].copy_from_slice(record);
+
+            unsafe {
+                region.store(offset, record);
+
+                let mut copied = [0u8; 64];
+                region.load(offset, copied.as_mut_ptr(), len);
+                assert_eq!(&copied[..len], record);
+
+                let mut copied = [0u8; 59];
+                region.load(0, copied.as_mut_ptr(), copied.len());
+                assert_eq!(copied, local);
+            }
+        }
+
+        let message = Message::new([0u8; 32]);
+        message.store([1; 32]);
+        assert_eq!(unsafe { message.load().assume_init() }, [1; 32]);
+
+        let mut expected = [1; 32];
+        expected[11..14].copy_from_slice(&[2; 3]);
+
+        unsafe { message.store_at(11, &[2; 3]) };
+        assert_eq!(unsafe { message.load().assume_init() }, expected);
+    }
+
+    #[test]
+    fn test_copy_explicit_padding() {
+        // Padding has to be spelled out as a field, so that every byte is initialized.
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        #[repr(C)]
+        struct Spelled {
+            tag: u8,
+            pad: [u8; 7],
+            val: u64,
+        }
+
+        unsafe impl NoUninit for Spelled {}
+
+        let val = Spelled {
+            tag: 1,
+            pad: [0; 7],
+            val: 2,
+        };
+
+        let message = Message::new(val);
+        assert_eq!(unsafe { message.load().assume_init() }, val);
+
+        unsafe { message.store_at(8, &3u64.to_ne_bytes()) };
+        assert_eq!(unsafe { message.load().assume_init() }.val, 3);
+    }
+}
@@ -0,0 +1,245 @@
+//! A [`RingBuffer`](crate::RingBuffer) whose blocks live on the heap, allowing its capacity to
+//! be chosen at runtime.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{
+    AtomicBool, AtomicUsize, Block, LockError, Message, NoUninit, Ordering, Padded, Reader, Ring,
+    Start, Writer,
+};
+
+/// A heap-allocated, non-write-blocking, ring buffer, that behaves like a
+/// SPMC queue and can be safely shared across threads.
+/// It follows the same protocol as [`RingBuffer`](crate::RingBuffer), but its capacity is
+/// set at runtime.
+#[derive(Debug)]
+pub struct HeapRingBuffer<T: NoUninit> {
+    locked: Padded<AtomicBool>,
+    version: Padded<AtomicUsize>,
+    index: Padded<AtomicUsize>,
+    data: Box<[Block<T>]>,
+}
+
+unsafe impl<T: NoUninit> Send for HeapRingBuffer<T> {}
+unsafe impl<T: NoUninit> Sync for HeapRingBuffer<T> {}
+
+impl<T: NoUninit> HeapRingBuffer<T> {
+    /// Constructs a new, empty buffer holding up to `capacity` messages.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: HeapRingBuffer<[u8; 16]> = HeapRingBuffer::with_capacity(1024);
+    /// ```
+    pub fn with_capacity(capacity: usize) -> HeapRingBuffer<T> {
+        assert!(
+            capacity > 0,
+            "the capacity of a HeapRingBuffer must not be 0"
+        );
+
+        let data: Vec<Block<T>> = (0..capacity)
+            .map(|_| Block {
+                seq: AtomicUsize::new(0),
+                laps: Message::zeroed(),
+                message: Message::zeroed(),
+            })
+            .collect();
+
+        HeapRingBuffer {
+            locked: Padded(AtomicBool::new(false)),
+            version: Padded(AtomicUsize::new(0)),
+            index: Padded(AtomicUsize::new(0)),
+            data: data.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the number of messages the buffer can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Reads the most recently published message without consuming anything. See
+    /// [`RingBuffer::latest`](crate::RingBuffer::latest).
+    #[inline]
+    pub fn latest(&self) -> Option<T> {
+        Ring::latest(self).map(|(_, _, (_, val))| val)
+    }
+
+    /// Tries to acquire the [`HeapRingBuffer`]'s [`HeapWriteGuard`]. As there can
+    /// only ever be one thread holding a [`HeapWriteGuard`], this fails if another thread is
+    /// already holding the lock.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: HeapRingBuffer<[u8; 16]> = HeapRingBuffer::with_capacity(1024);
+    ///
+    /// let Ok(mut writer) = buffer.try_lock() else { return };
+    /// ```
+    #[inline]
+    pub fn try_lock(&self) -> Result<HeapWriteGuard<'_, T>, LockError> {
+        if self.try_acquire() {
+            Ok(HeapWriteGuard { buffer: self })
+        } else {
+            Err(LockError::new())
+        }
+    }
+
+    /// Creates a new [`HeapSharedReader`] which provides shared read access of the queue. The
+    /// progress of this [`HeapSharedReader`] is not affected by other
+    /// [`HeapSharedReader`]s and does not affect them in turn.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: HeapRingBuffer<[u8; 16]> = HeapRingBuffer::with_capacity(1024);
+    ///
+    /// let reader = buffer.reader();
+    /// ```
+    #[inline]
+    pub fn reader(&self) -> HeapSharedReader<'_, T> {
+        Reader::new(self, 0, self.version.load(Ordering::Relaxed))
+    }
+
+    /// Creates a new [`HeapSharedReader`] that starts reading at `start`. See
+    /// [`RingBuffer::reader_from`](crate::RingBuffer::reader_from).
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: HeapRingBuffer<u32> = HeapRingBuffer::with_capacity(4);
+    ///
+    /// buffer.try_lock().unwrap().push_slice(&[1, 2, 3]);
+    ///
+    /// assert_eq!(buffer.reader_from(Start::Oldest).pop_front(), Some(1));
+    /// ```
+    #[inline]
+    pub fn reader_from(&self, start: Start) -> HeapSharedReader<'_, T> {
+        let (index, version) = self.position(start);
+        Reader::new(self, index, version)
+    }
+}
+
+impl<T: NoUninit> Ring<T> for HeapRingBuffer<T> {
+    #[inline]
+    fn version(&self) -> &AtomicUsize {
+        &self.version
+    }
+
+    #[inline]
+    fn index(&self) -> &AtomicUsize {
+        &self.index
+    }
+
+    #[inline]
+    fn data(&self) -> &[Block<T>] {
+        &self.data
+    }
+}
+
+impl<T: NoUninit> Writer<T> for HeapRingBuffer<T> {
+    #[inline]
+    fn locked(&self) -> &AtomicBool {
+        &self.locked
+    }
+}
+
+/// A [`Reader`] borrowing its [`HeapRingBuffer`].
+pub type HeapSharedReader<'read, T> = Reader<T, &'read HeapRingBuffer<T>>;
+
+/// Provides exclusive write access to the [`HeapRingBuffer`].
+#[derive(Debug)]
+pub struct HeapWriteGuard<'write, T: NoUninit> {
+    buffer: &'write HeapRingBuffer<T>,
+}
+
+unsafe impl<'write, T: NoUninit> Send for HeapWriteGuard<'write, T> {}
+
+impl<'write, T: NoUninit> HeapWriteGuard<'write, T> {
+    /// Push a new value to the back of the queue. This operation does not block. Returns the
+    /// sequence number of the message.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: HeapRingBuffer<[u8; 3]> = HeapRingBuffer::with_capacity(1024);
+    ///
+    /// if let Ok(mut writer) = buffer.try_lock() {
+    ///     writer.push_back([12, 21, 04]);
+    /// };
+    /// ```
+    #[inline]
+    pub fn push_back(&mut self, val: T) -> u64 {
+        self.buffer.push(val)
+    }
+
+    /// Pushes all values to the back of the queue in order, returning the range of their
+    /// sequence numbers. See [`WriteGuard::push_slice`](crate::WriteGuard::push_slice).
+    #[inline]
+    pub fn push_slice(&mut self, vals: &[T]) -> Range<u64> {
+        self.buffer.push_all(vals.iter().copied())
+    }
+}
+
+impl<'write, T: NoUninit> Extend<T> for HeapWriteGuard<'write, T> {
+    /// Pushes all values to the back of the queue. See [`HeapWriteGuard::push_slice`].
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.buffer.push_all(iter);
+    }
+}
+
+impl<'write, T: NoUninit> Drop for HeapWriteGuard<'write, T> {
+    fn drop(&mut self) {
+        self.buffer.release();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PopError;
+    extern crate std;
+
+    #[test]
+    fn test_heap_read() {
+        let buffer = HeapRingBuffer::with_capacity(5);
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        assert!(buffer.try_lock().is_err());
+
+        for i in 0..7 {
+            writer.push_back(i);
+        }
+
+        assert_eq!(reader.try_pop_front(), Err(PopError::Lagged { missed: 5 }));
+        assert_eq!(reader.pop_front_with_seq(), Some((5, 5)));
+        assert_eq!(reader.pop_front_with_seq(), Some((6, 6)));
+        assert_eq!(reader.pop_front(), None);
+    }
+
+    #[test]
+    fn test_heap_multi_reader() {
+        let buffer = HeapRingBuffer::with_capacity(100);
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+        let read = AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            let reader = &reader;
+            let read = &read;
+            for _ in 0..4 {
+                s.spawn(move || {
+                    while read.load(Ordering::Relaxed) < 100 {
+                        if reader.pop_front().is_some() {
+                            read.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+
+            for i in 0..100 {
+                writer.push_back([i; 4]);
+            }
+        });
+    }
+}
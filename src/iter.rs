@@ -0,0 +1,194 @@
+//! Iterating over the messages of a [`Reader`], rather than popping them one by one.
+
+use core::iter::FusedIterator;
+
+use core::ops::Deref;
+
+use crate::{NoUninit, Reader, Ring};
+
+#[allow(private_bounds)]
+impl<T: NoUninit, B> Reader<T, B>
+where
+    B: Deref,
+    B::Target: Ring<T>,
+{
+    /// Returns an iterator that pops elements from the front until the queue is empty. Just like
+    /// [`Reader::pop_front`], threads sharing the [`Reader`] steal elements from one
+    /// another while draining it.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_slice(&[1, 2, 3]);
+    ///
+    /// assert_eq!(reader.drain().sum::<u32>(), 6);
+    /// assert_eq!(reader.pop_front(), None);
+    /// ```
+    #[inline]
+    pub fn drain(&self) -> Drain<'_, T, B> {
+        Drain { reader: self }
+    }
+
+    /// Returns an iterator over the elements available to this [`Reader`], without popping
+    /// them. The elements are read from a clone of the reader, so neither this [`Reader`]
+    /// nor the threads sharing it notice.
+    ///
+    /// Elements published while iterating may be included as well, but the iterator never yields
+    /// more than a single lap of the [`RingBuffer`](crate::RingBuffer).
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_slice(&[1, 2, 3]);
+    ///
+    /// assert!(reader.iter_available().eq([1, 2, 3]));
+    /// assert_eq!(reader.pop_front(), Some(1));
+    /// ```
+    #[inline]
+    pub fn iter_available(&self) -> Available<T, B>
+    where
+        B: Clone,
+    {
+        Available {
+            reader: self.clone(),
+            remaining: self.buffer.data().len(),
+        }
+    }
+}
+
+/// Pops elements from the front of a [`Reader`] until the queue is empty. See
+/// [`Reader::drain`].
+#[derive(Debug)]
+pub struct Drain<'a, T: NoUninit, B> {
+    reader: &'a Reader<T, B>,
+}
+
+#[allow(private_bounds)]
+impl<T: NoUninit, B> Iterator for Drain<'_, T, B>
+where
+    B: Deref,
+    B::Target: Ring<T>,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        self.reader.pop_front()
+    }
+}
+
+/// Reads the elements available to a [`Reader`] without popping them. See
+/// [`Reader::iter_available`].
+#[derive(Debug)]
+pub struct Available<T: NoUninit, B> {
+    reader: Reader<T, B>,
+    /// How many more elements we may yield, so that a busy writer cannot keep us going forever.
+    remaining: usize,
+}
+
+#[allow(private_bounds)]
+impl<T: NoUninit, B> Iterator for Available<T, B>
+where
+    B: Deref,
+    B::Target: Ring<T>,
+{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        match self.reader.pop_front() {
+            Some(val) => {
+                self.remaining -= 1;
+                Some(val)
+            }
+            None => {
+                // Once we have caught up, we stay done.
+                self.remaining = 0;
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+#[allow(private_bounds)]
+impl<T: NoUninit, B> FusedIterator for Available<T, B>
+where
+    B: Deref,
+    B::Target: Ring<T>,
+{
+}
+
+#[cfg(test)]
+mod test {
+    use crate::RingBuffer;
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_drain_shared() {
+        let buffer = RingBuffer::<_, 256>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        writer.extend(0..200u32);
+
+        let mut read: Vec<_> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| s.spawn(|| reader.drain().collect::<Vec<_>>()))
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+
+        // Threads draining the same reader never read the same element twice.
+        read.sort();
+        assert_eq!(read, (0..200).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_iter_available() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        assert_eq!(reader.iter_available().next(), None);
+
+        writer.extend(0..6u32);
+
+        // The writer has lapped the reader, which skips ahead just like `pop_front`.
+        let mut available = reader.iter_available();
+        assert!(available.by_ref().eq([4, 5]));
+        assert_eq!(available.next(), None);
+
+        // A busy writer cannot keep the iterator going for more than a lap.
+        let mut available = reader.iter_available();
+        let mut yielded = 0;
+        for i in 6..20 {
+            writer.push_back(i);
+            yielded += available.next().is_some() as usize;
+        }
+        assert_eq!(yielded, 4);
+
+        assert_eq!(reader.pop_front(), Some(16));
+    }
+}
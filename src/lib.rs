@@ -2,6 +2,9 @@
 //! a fast and non-writer-blocking SPMC-queue, where all consumers read all
 //! messages.
 //!
+//! Messages are copied in and out of the queue word by word, so their type has to implement
+//! [`NoUninit`], which rules out padding.
+//!
 //! # Usage
 //!
 //! There are two ways of consuming from the queue. If threads share a
@@ -37,6 +40,41 @@
 //!     }
 //! });
 //! ```
+//!
+//! When every consumer should read every message, give each of them a [`Subscriber`] instead.
+//! A [`Subscriber`] pops through `&mut self`, so it cannot accidentally be shared, and does not
+//! need to compete with other threads for its progress.
+//!
+//! ```rust
+//! # use sling::*;
+//!
+//! let buffer = RingBuffer::<_, 256>::new();
+//!
+//! let mut writer = buffer.try_lock().unwrap();
+//! let subscriber = buffer.subscriber();
+//!
+//! std::thread::scope(|s| {
+//!     for t in 0..8 {
+//!         let mut subscriber = subscriber.clone();
+//!         s.spawn(move || {
+//!             for _ in 0..100 {
+//!                 if let Some(val) = subscriber.pop_front() {
+//!                     println!("t: {}, val: {:?}", t, val);
+//!                 };
+//!             }
+//!         });
+//!     }
+//!
+//!     for i in 0..100 {
+//!         writer.push_back([i, i, i]);
+//!     }
+//! });
+//! ```
+//!
+//! A [`RingBuffer`] only ever has a single writer. Should several producers need to publish into
+//! the same stream, use a [`MultiRingBuffer`] instead, whose [`MultiWriter`]s do not need to take
+//! a lock.
+//!
 //! # Important!
 //!
 //! It is also important to keep in mind, that slow readers will be overrun by the writer if they
@@ -44,6 +82,21 @@
 //! not large enough. It is advisable to test applications on a case-by-case basis and find a
 //! buffer size that is optimal to your use-case.
 //!
+//! # Features
+//!
+//! - `alloc`: Enables the `HeapRingBuffer`, whose capacity is chosen at runtime, as well as the
+//!   `OwnedWriteGuard` and `OwnedReader`, which keep an `Arc<RingBuffer>` alive. Also lets
+//!   readers hold back the writer through `RingBuffer::register` and
+//!   `WriteGuard::try_push_back`, rather than being overrun.
+//! - `async`: Lets readers wait for new messages asynchronously, through `SharedReader::recv`
+//!   or as a `Stream` of messages. Implies `alloc`.
+//! - `std`: Lets reader threads park until new messages arrive, through
+//!   `SharedReader::pop_front_blocking` and `SharedReader::pop_front_timeout`, and writer threads
+//!   park until the lock is free, through `RingBuffer::lock` and `RingBuffer::try_lock_for`.
+//!   Implies `alloc`.
+//! - `shm`: Enables the `ShmRingBuffer`, which lives in POSIX shared memory so that the writer
+//!   and its readers can run in separate processes. Only available on unix. Implies `std`.
+//!
 
 #![warn(missing_docs)]
 #![no_std]
@@ -52,26 +105,77 @@
 #![cfg_attr(feature = "nightly", feature(const_ptr_read))]
 #![cfg_attr(feature = "nightly", feature(const_refs_to_cell))]
 
-#[cfg(not(loom))]
-use core::cell::UnsafeCell;
+#[cfg(any(feature = "alloc", loom))]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+mod bytes;
+mod cell;
+mod copy;
+mod iter;
+mod multi;
+mod subscriber;
+
+use copy::Message;
+
+pub use bytes::{BytePopError, ByteReader, ByteRing, ByteWriteGuard};
+pub use cell::{SeqLockCell, SeqLockGuard};
+pub use copy::NoUninit;
+pub use iter::{Available, Drain};
+pub use multi::{MultiReader, MultiRingBuffer, MultiWriter};
+pub use subscriber::Subscriber;
+
+#[cfg(feature = "alloc")]
+mod bounded;
+#[cfg(feature = "alloc")]
+mod heap;
+#[cfg(feature = "alloc")]
+mod owned;
+
+#[cfg(feature = "alloc")]
+use bounded::Registry;
+#[cfg(feature = "alloc")]
+pub use bounded::{Full, RegisteredReader};
+#[cfg(feature = "alloc")]
+pub use heap::{HeapRingBuffer, HeapSharedReader, HeapWriteGuard};
+#[cfg(feature = "alloc")]
+pub use owned::{OwnedReader, OwnedWriteGuard};
+
+#[cfg(feature = "std")]
+mod blocking;
+#[cfg(all(feature = "shm", unix, not(loom)))]
+mod shm;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(any(feature = "async", feature = "std"))]
+mod wait;
+
+#[cfg(all(feature = "shm", unix, not(loom)))]
+pub use shm::{ShmReader, ShmRingBuffer, ShmWriteGuard};
+#[cfg(feature = "async")]
+pub use stream::ReaderStream;
+#[cfg(any(feature = "async", feature = "std"))]
+use wait::WaitList;
+
 use core::default::Default;
 use core::fmt::{Debug, Display};
-use core::mem::MaybeUninit;
-use core::ops::{Deref, DerefMut};
-use core::ptr::{read_volatile, write_bytes, write_volatile};
+use core::marker::PhantomData;
+use core::mem::{size_of, MaybeUninit};
+use core::ops::{Deref, DerefMut, Range};
 #[cfg(not(loom))]
-use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-#[cfg(loom)]
-use loom::cell::UnsafeCell;
+use core::ptr::write_bytes;
+#[cfg(not(loom))]
+use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
 #[cfg(loom)]
-use loom::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use loom::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
 
 /// A fixed-size, non-write-blocking, ring buffer, that behaves like a
 /// SPMC queue and can be safely shared across threads.
 /// It is limited to only work for types that are copy, as multiple
 /// threads can read the same message.
 #[derive(Debug)]
-pub struct RingBuffer<T: Copy, const N: usize> {
+pub struct RingBuffer<T: NoUninit, const N: usize> {
     // what else goes here?
     // version?
     // TODO(Emil): Can we make sure this is properly aligned for cache loads?
@@ -79,18 +183,26 @@ pub struct RingBuffer<T: Copy, const N: usize> {
     version: Padded<AtomicUsize>,
     index: Padded<AtomicUsize>,
     data: [Block<T>; N],
+    #[cfg(feature = "alloc")]
+    readers: Padded<Registry>,
+    #[cfg(any(feature = "async", feature = "std"))]
+    wakers: Padded<WaitList>,
+    #[cfg(feature = "std")]
+    writers: Padded<WaitList>,
+    #[cfg(feature = "std")]
+    holder: HolderSlot,
 }
 
-impl<T: Copy, const N: usize> Default for RingBuffer<T, N> {
+impl<T: NoUninit, const N: usize> Default for RingBuffer<T, N> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-unsafe impl<T: Copy, const N: usize> Send for RingBuffer<T, N> {}
-unsafe impl<T: Copy, const N: usize> Sync for RingBuffer<T, N> {}
+unsafe impl<T: NoUninit, const N: usize> Send for RingBuffer<T, N> {}
+unsafe impl<T: NoUninit, const N: usize> Sync for RingBuffer<T, N> {}
 
-impl<T: Copy, const N: usize> RingBuffer<T, N> {
+impl<T: NoUninit, const N: usize> RingBuffer<T, N> {
     /// Const constructor that only works on nightly with this crates `nightly` feature
     /// enabled. Constructs an empty queue of fixed length.
     #[cfg(feature = "nightly")]
@@ -103,11 +215,7 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
 
             // This workaround is currently necessary, as `core::mem::transmute()` is not available
             // for arrays whose length is specified by Const Generics.
-            let init = core::ptr::read(
-                (&data as *const [MaybeUninit<Block<T>>; N]).cast::<[Block<T>; N]>(),
-            );
-            core::mem::forget(data);
-            init
+            core::ptr::read((&data as *const [MaybeUninit<Block<T>>; N]).cast::<[Block<T>; N]>())
         };
 
         RingBuffer {
@@ -115,6 +223,14 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
             version: Padded(AtomicUsize::new(0)),
             index: Padded(AtomicUsize::new(0)),
             data,
+            #[cfg(feature = "alloc")]
+            readers: Padded(Registry::new()),
+            #[cfg(any(feature = "async", feature = "std"))]
+            wakers: Padded(WaitList::new()),
+            #[cfg(feature = "std")]
+            writers: Padded(WaitList::new()),
+            #[cfg(feature = "std")]
+            holder: HolderSlot::new(),
         }
     }
 
@@ -133,11 +249,7 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
 
             // This workaround is currently necessary, as `core::mem::transmute()` is not available
             // for arrays whose length is specified by Const Generics.
-            let init = core::ptr::read(
-                (&data as *const [MaybeUninit<Block<T>>; N]).cast::<[Block<T>; N]>(),
-            );
-            core::mem::forget(data);
-            init
+            core::ptr::read((&data as *const [MaybeUninit<Block<T>>; N]).cast::<[Block<T>; N]>())
         };
 
         RingBuffer {
@@ -145,6 +257,14 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
             version: Padded(AtomicUsize::new(0)),
             index: Padded(AtomicUsize::new(0)),
             data,
+            #[cfg(feature = "alloc")]
+            readers: Padded(Registry::new()),
+            #[cfg(any(feature = "async", feature = "std"))]
+            wakers: Padded(WaitList::new()),
+            #[cfg(feature = "std")]
+            writers: Padded(WaitList::new()),
+            #[cfg(feature = "std")]
+            holder: HolderSlot::new(),
         }
     }
 
@@ -152,36 +272,29 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
     #[cfg(loom)]
     pub fn new() -> RingBuffer<T, N> {
         // Initialize the array.
-        let data: [Block<T>; N] = unsafe {
-            let mut data: [MaybeUninit<Block<T>>; N] = MaybeUninit::uninit().assume_init();
-            for b in data.iter_mut() {
-                core::ptr::write(
-                    (b as *mut MaybeUninit<Block<T>>).cast::<Block<T>>(),
-                    Block {
-                        seq: AtomicUsize::new(0),
-                        message: UnsafeCell::new(MaybeUninit::uninit().assume_init()),
-                    },
-                )
-            }
-
-            // This workaround is currently necessary, as `core::mem::transmute()` is not available
-            // for arrays whose length is specified by Const Generics.
-            let init = core::ptr::read(
-                (&data as *const [MaybeUninit<Block<T>>; N]).cast::<[Block<T>; N]>(),
-            );
-            core::mem::forget(data);
-            init
-        };
+        let data: [Block<T>; N] = core::array::from_fn(|_| Block {
+            seq: AtomicUsize::new(0),
+            laps: Message::zeroed(),
+            message: Message::zeroed(),
+        });
 
         RingBuffer {
             locked: Padded(AtomicBool::new(false)),
             version: Padded(AtomicUsize::new(0)),
             index: Padded(AtomicUsize::new(0)),
             data,
+            #[cfg(feature = "alloc")]
+            readers: Padded(Registry::new()),
+            #[cfg(any(feature = "async", feature = "std"))]
+            wakers: Padded(WaitList::new()),
+            #[cfg(feature = "std")]
+            writers: Padded(WaitList::new()),
+            #[cfg(feature = "std")]
+            holder: HolderSlot::new(),
         }
     }
 
-    /// Tries to acquire the [`RingBuffer's`] [`WriteGuard`]. As there can
+    /// Tries to acquire the [`RingBuffer`]'s [`WriteGuard`]. As there can
     /// only ever be one thread holding a [`WriteGuard`], this fails if another thread is
     /// already holding the lock.
     /// ```rust
@@ -189,13 +302,47 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
     /// let buffer: RingBuffer<[u8; 16], 1024> = RingBuffer::new();
     ///
     /// let Ok(mut writer) = buffer.try_lock() else { return };
+    ///
+    /// assert!(buffer.try_lock().is_err());
     /// ```
     #[inline]
-    pub fn try_lock(&self) -> Result<WriteGuard<'_, T, N>, ()> {
-        if !self.locked.swap(true, Ordering::Acquire) {
+    pub fn try_lock(&self) -> Result<WriteGuard<'_, T, N>, LockError> {
+        if self.try_acquire() {
             Ok(WriteGuard { buffer: self })
         } else {
-            Err(())
+            Err(self.lock_error())
+        }
+    }
+
+    /// The error for failing to take the lock, naming its current holder if we know it.
+    #[inline]
+    fn lock_error(&self) -> LockError {
+        LockError {
+            #[cfg(feature = "std")]
+            holder: self.holder.get(),
+        }
+    }
+
+    /// Acquires the [`RingBuffer`]'s [`WriteGuard`], spinning for as long as another thread is
+    /// holding it. This suits handing the writer over between threads on failover, without
+    /// giving up the core. With the `std` feature, `RingBuffer::lock` parks the thread instead.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<[u8; 16], 1024> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.lock_spin();
+    /// ```
+    pub fn lock_spin(&self) -> WriteGuard<'_, T, N> {
+        loop {
+            if let Ok(writer) = self.try_lock() {
+                return writer;
+            }
+
+            // Only try again once the lock looks free, so we do not keep stealing the cache line
+            // from the current writer.
+            while self.locked.load(Ordering::Relaxed) {
+                spin_loop();
+            }
         }
     }
 
@@ -211,225 +358,1626 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
     /// ```
     #[inline]
     pub fn reader(&self) -> SharedReader<'_, T, N> {
-        SharedReader {
-            buffer: Padded(self),
-            index: Padded(AtomicUsize::new(0)),
-            version: Padded(AtomicUsize::new(self.version.load(Ordering::Relaxed))),
-        }
+        Reader::new(self, 0, self.version.load(Ordering::Relaxed))
     }
 
-    /// Increments the sequence at the current index by 1, making it odd, prohibiting reads.
+    /// Creates a new [`SharedReader`] that starts reading at `start`, rather than wherever the
+    /// writer happens to be. Should the writer already have overwritten the message to start at,
+    /// the reader skips ahead just as if it had been overrun.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 4> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    ///
+    /// writer.push_slice(&[0, 1, 2, 3, 4, 5]);
+    ///
+    /// assert_eq!(buffer.reader_from(Start::Oldest).pop_front(), Some(2));
+    /// assert_eq!(buffer.reader_from(Start::Seq(3)).pop_front(), Some(3));
+    ///
+    /// let newest = buffer.reader_from(Start::Newest);
+    /// assert_eq!(newest.pop_front(), None);
+    ///
+    /// writer.push_back(6);
+    /// assert_eq!(newest.pop_front(), Some(6));
+    /// ```
     #[inline]
-    fn start_write(&self) -> usize {
-        let index = self.index.load(Ordering::Relaxed);
-        let seq = self.data[index].seq.fetch_add(1, Ordering::Relaxed);
-
-        // Make sure the state is consistent.
-        assert!(seq % 2 == 0);
-
-        // Update the global version to be at newer than the current block version.
-        let ver = self.version.load(Ordering::Relaxed);
-        self.version
-            .store(core::cmp::max(ver, seq + 2), Ordering::Relaxed);
-
-        index
+    pub fn reader_from(&self, start: Start) -> SharedReader<'_, T, N> {
+        let (index, version) = self.position(start);
+        Reader::new(self, index, version)
     }
 
-    /// Increments the sequence at the current index by 1, making it even and allowing reads.
+    /// Reads the most recently published message without consuming anything, or returns `None`
+    /// if nothing has been published yet. This suits consumers that only ever care about the
+    /// newest value, rather than the queue.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    ///
+    /// assert_eq!(buffer.latest(), None);
+    ///
+    /// writer.push_slice(&[1, 2, 3]);
+    ///
+    /// assert_eq!(buffer.latest(), Some(3));
+    /// ```
     #[inline]
-    fn end_write(&self, index: usize) {
-        self.index.store((index + 1) % N, Ordering::Relaxed);
-        let seq = self.data[index].seq.fetch_add(1, Ordering::Release);
+    pub fn latest(&self) -> Option<T> {
+        Ring::latest(self).map(|(_, _, (_, val))| val)
+    }
 
-        // Ensure a consistent state.
-        assert!(seq % 2 == 1);
+    /// Returns the number of messages published so far, which is also the sequence number the
+    /// next message is going to get. Messages of a batch the writer is still publishing may not
+    /// be counted yet.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 4> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    ///
+    /// assert_eq!(buffer.published(), 0);
+    ///
+    /// writer.push_slice(&[1, 2, 3, 4, 5, 6]);
+    ///
+    /// assert_eq!(buffer.published(), 6);
+    /// ```
+    #[inline]
+    pub fn published(&self) -> u64 {
+        Ring::published(self)
     }
 }
 
 /// Shared read access to its buffer. When multiple threads consume from the
-/// [`RingBuffer`] throught the same [`SharedReader`], they will share progress
-/// on the queue. Distinct [`RingBuffers`] do not share progress.
+/// buffer throught the same [`Reader`], they will share progress on the queue. Distinct
+/// [`Reader`]s do not share progress. If every consumer should read every message, prefer a
+/// [`Subscriber`] each.
+///
+/// The reader is generic over the handle `B` it reads the buffer through, so the same reader
+/// serves every layout: [`SharedReader`] borrows a [`RingBuffer`], while
+/// `OwnedReader` keeps it alive through an `Arc`.
 #[derive(Debug)]
-pub struct SharedReader<'read, T: Copy, const N: usize> {
-    buffer: Padded<&'read RingBuffer<T, N>>,
+pub struct Reader<T: NoUninit, B> {
+    buffer: Padded<B>,
     index: Padded<AtomicUsize>,
     version: Padded<AtomicUsize>,
+    /// The number of messages the writer overran us by so far.
+    missed: AtomicUsize,
+    _marker: PhantomData<fn() -> T>,
 }
 
-/// Clones a [`RingBuffer`], creating a new one that does not share progress with the
-/// original [`RingBuffer`].
-impl<'read, T: Copy, const N: usize> Clone for SharedReader<'read, T, N> {
+/// A [`Reader`] borrowing its [`RingBuffer`].
+pub type SharedReader<'read, T, const N: usize> = Reader<T, &'read RingBuffer<T, N>>;
+
+/// Clones a [`Reader`], creating a new one that does not share progress with the
+/// original [`Reader`].
+impl<T: NoUninit, B: Clone> Clone for Reader<T, B> {
     fn clone(&self) -> Self {
-        SharedReader {
-            buffer: Padded(&self.buffer),
+        Reader {
+            buffer: Padded(self.buffer.0.clone()),
             index: Padded(AtomicUsize::new(self.index.load(Ordering::Relaxed))),
             version: Padded(AtomicUsize::new(self.version.load(Ordering::Relaxed))),
+            missed: AtomicUsize::new(self.missed.load(Ordering::Relaxed)),
+            _marker: PhantomData,
         }
     }
 }
 
-unsafe impl<'read, T: Copy, const N: usize> Send for SharedReader<'read, T, N> {}
+impl<T: NoUninit, B> Reader<T, B> {
+    /// Creates a reader of `buffer` that starts out at `index` and `version`.
+    pub(crate) fn new(buffer: B, index: usize, version: usize) -> Self {
+        Reader {
+            buffer: Padded(buffer),
+            index: Padded(AtomicUsize::new(index)),
+            version: Padded(AtomicUsize::new(version)),
+            missed: AtomicUsize::new(0),
+            _marker: PhantomData,
+        }
+    }
+}
 
-impl<'read, T: Copy, const N: usize> SharedReader<'read, T, N> {
+// `Ring` is private on purpose, as it seals the buffers a `Reader` can read from.
+#[allow(private_bounds)]
+impl<T: NoUninit, B> Reader<T, B>
+where
+    B: Deref,
+    B::Target: Ring<T>,
+{
     /// Pops the next element from the front. The element is only popped for us and other threads
     /// will still need to pop this for themselves.
+    ///
+    /// Should the writer have overrun this [`SharedReader`], the reader silently skips ahead to
+    /// the writer's most recent lap. Use [`SharedReader::try_pop_front`] to be notified
+    /// of lost messages instead.
     pub fn pop_front(&self) -> Option<T> {
-        // Checks if data if we are currently caught up.
-        // This is acquire as we want to make sure that we are syncing up the readers version with
-        // the last increment of index. Otherwise we may end up reading old data.
-        let mut i = self.index.load(Ordering::Acquire);
+        self.pop_front_with_seq().map(|(_, val)| val)
+    }
 
+    /// Pops the next element from the front alongside its sequence number, as returned by
+    /// [`WriteGuard::push_back`]. Gaps in the sequence numbers indicate messages this
+    /// [`SharedReader`] has missed, or which were popped by another thread sharing it.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<char, 4> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_back('a');
+    /// writer.push_back('b');
+    ///
+    /// assert_eq!(reader.pop_front_with_seq(), Some((0, 'a')));
+    /// assert_eq!(reader.pop_front_with_seq(), Some((1, 'b')));
+    /// ```
+    pub fn pop_front_with_seq(&self) -> Option<(u64, T)> {
         loop {
-            let ver = self.version.load(Ordering::Relaxed);
-
-            // Ensures we are not reading old data, or data that is currently being written to.
-            // This is `Acquire` so we observed the write to data should seq1 == seq2.
-            let seq1 = unsafe {
-                Self::check_version(
-                    self.buffer
-                        .data
-                        .get_unchecked(i)
-                        .seq
-                        .load(Ordering::Acquire),
-                    ver,
-                    i,
-                )?
-            };
+            match self.try_pop() {
+                Ok(message) => return Some(message),
+                Err(PopError::Lagged { .. }) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
 
-            // On failure we end here, as we have an outdated version and thus are reading consumed
-            // data.
-            self.version
-                .compare_exchange(ver, seq1, Ordering::Relaxed, Ordering::Relaxed)
-                .ok()?;
+    /// Tries to pop the next element from the front, reporting why no element could be popped
+    /// on failure.
+    ///
+    /// If the writer has overrun this [`SharedReader`], [`PopError::Lagged`] is returned and the
+    /// reader is moved ahead to the writer's most recent lap, such that the next call resumes
+    /// from there.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 4> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// assert_eq!(reader.try_pop_front(), Err(PopError::Empty));
+    ///
+    /// for i in 0..6 {
+    ///     writer.push_back(i);
+    /// }
+    ///
+    /// assert_eq!(reader.try_pop_front(), Err(PopError::Lagged { missed: 4 }));
+    /// assert_eq!(reader.try_pop_front(), Ok(4));
+    /// ```
+    pub fn try_pop_front(&self) -> Result<T, PopError> {
+        self.try_pop().map(|(_, val)| val)
+    }
 
-            // If this fails, someone has already read the data. This is the only time we should
-            // retry the loop.
-            // This is `Release` on store to ensure that the new version of the `SharedReader` is
-            // observed by all sharing threads, and on failure we `Acquire` to ensure we get the
-            // latest version.
-            if let Err(new) =
-                self.index
-                    .compare_exchange(i, (i + 1) % N, Ordering::Release, Ordering::Acquire)
-            {
-                i = new;
-                continue;
-            }
+    /// Pops up to `buf.len()` elements from the front into `buf`, returning how many were
+    /// copied. Rather than claiming them one at a time, this claims a contiguous range of the
+    /// queue at once, which makes it much cheaper to drain a [`SharedReader`] shared by many
+    /// threads. Fewer elements than available may be popped when the range wraps around the end
+    /// of the [`RingBuffer`].
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_slice(&[1, 2, 3]);
+    ///
+    /// let mut buf = [0; 8];
+    /// assert_eq!(reader.pop_into(&mut buf), 3);
+    /// assert_eq!(buf[..3], [1, 2, 3]);
+    /// ```
+    pub fn pop_into(&self, buf: &mut [T]) -> usize {
+        let (copied, missed) = self.buffer.pop_into(&self.index, &self.version, buf);
+        self.count_missed(missed);
+        copied
+    }
 
-            // We cannot test the this part of the process with `loom`, as this operation is `UB`
-            // if data is written too while we are reading it; yet, due to the nature of seqlock,
-            // we discard the `UB` reads. Future versions of the compiler may optimize this code in
-            // a way that allows `UB` reads to leak past the seqlock, but currently this
-            // implementation is sane.
-            //
-            // # Safety: We ensure validity of the read with the equality check later.
-            #[cfg(not(loom))]
-            let data: T =
-                unsafe { read_volatile(self.buffer.data.get_unchecked(i).message.get().cast()) };
-
-            let seq2 = unsafe {
-                self.buffer
-                    .data
-                    .get_unchecked(i)
-                    .seq
-                    .load(Ordering::Relaxed)
-            };
+    /// Jumps ahead to the most recently published message and pops it, discarding every
+    /// message in between. Lagging consumers can use this to catch up with the writer, rather
+    /// than draining stale messages. Returns `None` if nothing has been published yet.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_slice(&[1, 2, 3]);
+    ///
+    /// assert_eq!(reader.skip_to_latest(), Some(3));
+    /// assert_eq!(reader.pop_front(), None);
+    ///
+    /// writer.push_back(4);
+    ///
+    /// assert_eq!(reader.pop_front(), Some(4));
+    /// ```
+    pub fn skip_to_latest(&self) -> Option<T> {
+        self.buffer.skip_to_latest(&self.index, &self.version)
+    }
 
-            if seq1 != seq2 {
-                return None;
+    /// Returns the next element without popping it, skipping ahead should the writer have
+    /// overrun us. Another thread sharing this [`SharedReader`] may still pop the element before
+    /// we do, so use [`SharedReader::pop_if`] to pop it only should it be the one we looked at.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_back(1);
+    ///
+    /// assert_eq!(reader.peek_front(), Some(1));
+    /// assert_eq!(reader.pop_front(), Some(1));
+    /// assert_eq!(reader.peek_front(), None);
+    /// ```
+    pub fn peek_front(&self) -> Option<T> {
+        loop {
+            match self.buffer.peek(&self.index, &self.version) {
+                Ok((_, _, _, (_, message))) => return Some(message),
+                Err(PopError::Lagged { missed }) => self.count_missed(missed),
+                Err(_) => return None,
             }
+        }
+    }
 
-            #[cfg(not(loom))]
-            return Some(data);
-            #[cfg(loom)]
-            return None;
+    /// Pops the next element from the front, but only if `f` accepts it. The element is read
+    /// before it is popped, and it is only popped should no other thread sharing this
+    /// [`SharedReader`] have popped it in the meantime. Should another thread have been faster,
+    /// `f` is asked about the element after it instead.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_slice(&[1, 2]);
+    ///
+    /// assert_eq!(reader.pop_if(|&val| val % 2 == 0), None);
+    /// assert_eq!(reader.pop_if(|&val| val % 2 == 1), Some(1));
+    /// assert_eq!(reader.pop_if(|&val| val % 2 == 0), Some(2));
+    /// ```
+    pub fn pop_if(&self, mut f: impl FnMut(&T) -> bool) -> Option<T> {
+        loop {
+            match self.buffer.pop_if(&self.index, &self.version, &mut f) {
+                Ok(message) => return message.map(|(_, val)| val),
+                Err(PopError::Lagged { missed }) => self.count_missed(missed),
+                Err(_) => return None,
+            }
         }
     }
 
-    /// Checks if we are reading data we have already consumed.
+    /// Returns the number of messages published to the [`RingBuffer`] that this
+    /// [`SharedReader`] has not popped yet. Should this exceed the length of the buffer, the
+    /// writer has already overrun us, and the next pop skips ahead.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 4> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_slice(&[1, 2, 3]);
+    /// assert_eq!(reader.lag(), 3);
+    ///
+    /// reader.pop_front();
+    /// assert_eq!(reader.lag(), 2);
+    /// ```
     #[inline]
-    fn check_version(mut seq: usize, ver: usize, i: usize) -> Option<usize> {
-        // The current version of the
-        if seq & 1 != 0 {
-            return None;
+    pub fn lag(&self) -> usize {
+        self.buffer.lag(&self.index, &self.version)
+    }
+
+    /// Returns the number of messages this [`SharedReader`] lost so far, because the writer
+    /// overran it. Messages skipped through [`SharedReader::skip_to_latest`] are not counted. The
+    /// count is kept in a `usize`, so on 32-bit targets it wraps around once it exceeds
+    /// `u32::MAX`.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 4> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_slice(&[0, 1, 2, 3, 4, 5]);
+    ///
+    /// assert_eq!(reader.pop_front(), Some(4));
+    /// assert_eq!(reader.missed(), 4);
+    /// ```
+    #[inline]
+    pub fn missed(&self) -> u64 {
+        self.missed.load(Ordering::Relaxed) as u64
+    }
+
+    /// Pops the next element alongside its sequence number, or reports why it could not.
+    #[inline]
+    fn try_pop(&self) -> Result<(u64, T), PopError> {
+        let res = self.buffer.try_pop(&self.index, &self.version);
+
+        if let Err(PopError::Lagged { missed }) = res {
+            self.count_missed(missed);
         }
 
-        // TODO(emilHof) This should not be needed!
-        seq &= usize::MAX - 1;
+        res
+    }
 
-        if (i == 0 && seq == ver) || seq < ver {
-            return None;
+    /// Adds `missed` messages to the ones we lost to the writer.
+    #[inline]
+    fn count_missed(&self, missed: usize) {
+        if missed > 0 {
+            self.missed.fetch_add(missed, Ordering::Relaxed);
         }
+    }
+}
+
+/// Where a reader created by [`RingBuffer::reader_from`] starts reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Start {
+    /// The oldest message the [`RingBuffer`] still holds, replaying everything it retained.
+    Oldest,
+    /// The next message to be published, skipping everything published so far.
+    Newest,
+    /// The message with the given sequence number, as returned by [`WriteGuard::push_back`].
+    Seq(u64),
+}
 
-        Some(seq)
+/// The reasons why [`SharedReader::try_pop_front`] did not return a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PopError {
+    /// There are no new messages in the queue.
+    Empty,
+    /// The writer has overrun the reader, and `missed` messages were skipped. The reader has been
+    /// moved ahead to the writer's most recent lap.
+    Lagged {
+        /// The number of messages that were skipped.
+        missed: usize,
+    },
+    /// Another thread sharing the [`SharedReader`] has updated its progress concurrently.
+    /// Retrying may succeed.
+    Contended,
+}
+
+impl Display for PopError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PopError::Empty => f.write_str("the queue is empty"),
+            PopError::Lagged { missed } => f.write_fmt(format_args!(
+                "the reader lagged behind and missed {missed} messages"
+            )),
+            PopError::Contended => f.write_str("the reader was contended by another thread"),
+        }
     }
 }
 
-/// Provides exclusive write access to the [`RingBuffer`].
-#[derive(Debug)]
-pub struct WriteGuard<'write, T: Copy, const N: usize> {
-    buffer: &'write RingBuffer<T, N>,
+impl core::error::Error for PopError {}
+
+/// Returned when the writer's lock could not be taken, as another thread is holding it. With the
+/// `std` feature, the error of a [`RingBuffer`] also tells who is holding the lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockError {
+    #[cfg(feature = "std")]
+    holder: Option<Holder>,
 }
 
-unsafe impl<'read, T: Copy, const N: usize> Send for WriteGuard<'read, T, N> {}
+impl LockError {
+    /// An error that does not know the holder of the lock.
+    #[inline]
+    pub(crate) const fn new() -> LockError {
+        LockError {
+            #[cfg(feature = "std")]
+            holder: None,
+        }
+    }
 
-impl<'write, T: Copy, const N: usize> WriteGuard<'write, T, N> {
-    /// Push a new value to the back of the queue. This operation does not block.
+    /// The writer holding the lock when it could not be taken. This is `None` if the writer does
+    /// not track its holder, or if the lock changed hands just as we failed to take it.
     /// ```rust
     /// # use sling::*;
-    /// let buffer: RingBuffer<[u8; 3], 1024> = RingBuffer::new();
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
     ///
-    /// if let Ok(mut writer) = buffer.try_lock() {
-    ///     writer.push_back([12, 21, 04])
-    /// };
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// writer.set_tag("primary");
+    ///
+    /// let holder = buffer.try_lock().unwrap_err().holder().unwrap();
+    ///
+    /// assert_eq!(holder.thread(), std::thread::current().id());
+    /// assert_eq!(holder.tag(), Some("primary"));
     /// ```
-    pub fn push_back(&mut self, val: T) {
-        let i = self.buffer.start_write();
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn holder(&self) -> Option<Holder> {
+        self.holder
+    }
+}
 
-        #[cfg(not(loom))]
-        unsafe {
-            write_volatile(self.buffer.data[i].message.get().cast(), val)
-        };
+impl Display for LockError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("the writer's lock is held by another writer")?;
 
-        #[cfg(loom)]
-        unsafe {
-            self.buffer.data[i]
-                .message
-                .with_mut(|p| write_volatile(p.cast(), val))
-        };
+        #[cfg(feature = "std")]
+        if let Some(holder) = self.holder {
+            f.write_fmt(format_args!(" ({holder})"))?;
+        }
 
-        self.buffer.end_write(i);
+        Ok(())
     }
 }
 
-impl<'write, T: Copy, const N: usize> Drop for WriteGuard<'write, T, N> {
-    fn drop(&mut self) {
-        self.buffer.locked.store(false, Ordering::Release);
-    }
+impl core::error::Error for LockError {}
+
+/// The writer holding a lock, as reported by [`LockError::holder`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Holder {
+    thread: std::thread::ThreadId,
+    tag: Option<&'static str>,
 }
 
-struct Block<T: Copy> {
-    seq: AtomicUsize,
-    message: UnsafeCell<MaybeUninit<T>>,
+#[cfg(feature = "std")]
+impl Holder {
+    /// The thread that took the lock.
+    #[inline]
+    pub fn thread(&self) -> std::thread::ThreadId {
+        self.thread
+    }
+
+    /// The tag the writer gave itself through [`WriteGuard::set_tag`], if any.
+    #[inline]
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
 }
 
-impl<T: Copy> Debug for Block<T> {
+#[cfg(feature = "std")]
+impl Display for Holder {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("Block")
-            .field("seq", &self.seq.load(Ordering::Relaxed))
-            .finish()
+        match self.tag {
+            Some(tag) => f.write_fmt(format_args!("{tag} on {:?}", self.thread)),
+            None => f.write_fmt(format_args!("{:?}", self.thread)),
+        }
     }
 }
 
-/// Aligns its contents to the cache line.
-#[cfg_attr(
-    any(
-        target_arch = "x86_64",
-        target_arch = "aarch64",
-        target_arch = "powerpc64",
-    ),
-    repr(align(128))
-)]
-#[cfg_attr(
+/// Remembers who is holding a writer's lock. This is only ever touched when the lock changes
+/// hands, so it does not slow down the writer.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct HolderSlot(std::sync::Mutex<Option<Holder>>);
+
+#[cfg(feature = "std")]
+impl HolderSlot {
+    const fn new() -> HolderSlot {
+        HolderSlot(std::sync::Mutex::new(None))
+    }
+
+    fn get(&self) -> Option<Holder> {
+        *self.lock()
+    }
+
+    /// Records the current thread as the holder, after it took the lock.
+    fn acquire(&self) {
+        *self.lock() = Some(Holder {
+            thread: std::thread::current().id(),
+            tag: None,
+        });
+    }
+
+    /// Forgets the holder, before it releases the lock.
+    fn release(&self) {
+        *self.lock() = None;
+    }
+
+    fn tag(&self, tag: &'static str) {
+        if let Some(holder) = self.lock().as_mut() {
+            holder.tag = Some(tag);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Holder>> {
+        // The slot is always consistent, even if a thread panicked while holding it.
+        self.0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Provides exclusive write access to the [`RingBuffer`].
+#[derive(Debug)]
+pub struct WriteGuard<'write, T: NoUninit, const N: usize> {
+    buffer: &'write RingBuffer<T, N>,
+}
+
+unsafe impl<'read, T: NoUninit, const N: usize> Send for WriteGuard<'read, T, N> {}
+
+impl<'write, T: NoUninit, const N: usize> WriteGuard<'write, T, N> {
+    /// Push a new value to the back of the queue. This operation does not block. Returns the
+    /// sequence number of the message, which starts at 0 and is incremented by 1 for every
+    /// message pushed to the [`RingBuffer`].
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<[u8; 3], 1024> = RingBuffer::new();
+    ///
+    /// if let Ok(mut writer) = buffer.try_lock() {
+    ///     assert_eq!(writer.push_back([12, 21, 04]), 0);
+    ///     assert_eq!(writer.push_back([12, 21, 04]), 1);
+    /// };
+    /// ```
+    #[inline]
+    pub fn push_back(&mut self, val: T) -> u64 {
+        self.buffer.push(val)
+    }
+
+    /// Pushes a new message to the back of the queue, letting `f` write it straight into the
+    /// [`RingBuffer`]'s slot, rather than passing it by value. Returns the sequence number of
+    /// the message.
+    ///
+    /// The slot still holds the message last written to it, or zeroed bytes, so `f` may only
+    /// update the fields that changed through [`SlotWriter::set`]. Readers only see the message
+    /// once `f` returns. Should `f` panic, the message is published as `f` left it.
+    /// ```rust
+    /// # use sling::*;
+    /// #[derive(Clone, Copy)]
+    /// #[repr(C)]
+    /// struct Snapshot {
+    ///     seq: u64,
+    ///     bids: [[u64; 2]; 256],
+    /// }
+    ///
+    /// unsafe impl NoUninit for Snapshot {}
+    ///
+    /// let buffer: RingBuffer<Snapshot, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_with(|slot| {
+    ///     slot.set(field!(Snapshot, seq), 7);
+    ///     slot.set(field!(Snapshot, bids).at(3), [100, 5]);
+    /// });
+    ///
+    /// let snapshot = reader.pop_front().unwrap();
+    /// assert_eq!((snapshot.seq, snapshot.bids[3]), (7, [100, 5]));
+    /// ```
+    #[inline]
+    pub fn push_with<F: FnOnce(&mut SlotWriter<'_, T>)>(&mut self, f: F) -> u64 {
+        self.buffer.push_with(f)
+    }
+
+    /// Pushes all values to the back of the queue in order, returning the range of their
+    /// sequence numbers. This is cheaper than pushing the values one at a time, as the
+    /// [`RingBuffer`]'s bookkeeping is only updated once for the whole batch. Readers may
+    /// already pop the first values while the rest are still being written.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u8, 1024> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    ///
+    /// assert_eq!(writer.push_slice(&[1, 2, 3]), 0..3);
+    /// assert_eq!(writer.push_slice(&[4, 5]), 3..5);
+    ///
+    /// writer.extend(6..10);
+    /// assert_eq!(writer.push_back(10), 9);
+    /// ```
+    #[inline]
+    pub fn push_slice(&mut self, vals: &[T]) -> Range<u64> {
+        self.buffer.push_all(vals.iter().copied())
+    }
+
+    /// Tags this writer, so that threads failing to take the lock can tell who is holding it
+    /// through [`LockError::holder`]. The tag is forgotten once the [`WriteGuard`] is dropped.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn set_tag(&mut self, tag: &'static str) {
+        self.buffer.holder.tag(tag);
+    }
+}
+
+impl<'write, T: NoUninit, const N: usize> Extend<T> for WriteGuard<'write, T, N> {
+    /// Pushes all values to the back of the queue. See [`WriteGuard::push_slice`].
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.buffer.push_all(iter);
+    }
+}
+
+impl<'write, T: NoUninit, const N: usize> Drop for WriteGuard<'write, T, N> {
+    fn drop(&mut self) {
+        self.buffer.release();
+    }
+}
+
+/// Writes a message straight into the block it is going to be published from. See
+/// [`WriteGuard::push_with`].
+pub struct SlotWriter<'slot, T: NoUninit> {
+    message: &'slot Message<T>,
+}
+
+impl<'slot, T: NoUninit> SlotWriter<'slot, T> {
+    /// Replaces the whole message with `val`.
+    #[inline]
+    pub fn write(&mut self, val: T) {
+        self.message.store(val);
+    }
+
+    /// Replaces the `field` of the message with `val`, leaving the rest of it as it is.
+    #[inline]
+    pub fn set<U: NoUninit>(&mut self, field: Field<T, U>, val: U) {
+        // # Safety: `Field` guarantees a `U` at its offset, so the message stays a valid `T`.
+        unsafe { self.message.store_at(field.offset, copy::bytes_of(&val)) };
+    }
+
+    /// Copies `vals` into the elements of the array `field`, starting at the element `start`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `vals` do not fit into the array from `start` on.
+    #[inline]
+    pub fn set_slice<U: NoUninit, const M: usize>(
+        &mut self,
+        field: Field<T, [U; M]>,
+        start: usize,
+        vals: &[U],
+    ) {
+        assert!(
+            start.checked_add(vals.len()).is_some_and(|end| end <= M),
+            "the values do not fit into the array"
+        );
+
+        // # Safety: The elements are all within the array, so the message stays a valid `T`.
+        unsafe {
+            self.message.store_at(
+                field.offset + start * size_of::<U>(),
+                copy::slice_bytes(vals),
+            )
+        };
+    }
+
+    /// Replaces the bytes of the message starting at `offset` with `bytes`, leaving all other
+    /// bytes as they are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `bytes` do not fit into a `T` at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// By the time the closure passed to [`WriteGuard::push_with`] returns or unwinds, the bytes
+    /// of the message must form a valid `T`.
+    #[inline]
+    pub unsafe fn write_at(&mut self, offset: usize, bytes: &[u8]) {
+        assert!(
+            offset
+                .checked_add(bytes.len())
+                .is_some_and(|end| end <= size_of::<T>()),
+            "the bytes do not fit into the message"
+        );
+
+        self.message.store_at(offset, bytes);
+    }
+}
+
+impl<'slot, T: NoUninit> Debug for SlotWriter<'slot, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SlotWriter").finish_non_exhaustive()
+    }
+}
+
+/// A field of type `U` within a message of type `T`, which a [`SlotWriter`] writes in place.
+/// Name one through the [`field!`] macro.
+pub struct Field<T, U> {
+    offset: usize,
+    _marker: PhantomData<fn(&T) -> &U>,
+}
+
+impl<T, U> Field<T, U> {
+    /// Creates the field at `offset`, whose type `project` infers. Use [`field!`] instead.
+    ///
+    /// # Safety
+    ///
+    /// Every `T` must hold the `U` that `project` returns at `offset`.
+    #[doc(hidden)]
+    #[inline]
+    pub const unsafe fn project(offset: usize, _project: fn(&T) -> &U) -> Field<T, U> {
+        Field {
+            offset,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Field<T, T> {
+    /// The whole message, which lets [`Field::at`] name the elements of an array message.
+    #[inline]
+    pub const fn message() -> Field<T, T> {
+        Field {
+            offset: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, U, const M: usize> Field<T, [U; M]> {
+    /// The element `index` of the array.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<[u64; 512], 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_with(|slot| slot.set(Field::message().at(7), 7));
+    ///
+    /// assert_eq!(reader.pop_front().unwrap()[7], 7);
+    /// ```
+    #[inline]
+    pub const fn at(self, index: usize) -> Field<T, U> {
+        assert!(index < M, "the index is out of bounds");
+
+        Field {
+            offset: self.offset + index * size_of::<U>(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, U> Clone for Field<T, U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, U> Copy for Field<T, U> {}
+
+impl<T, U> Debug for Field<T, U> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Field")
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+/// Names a field of a message type as a [`Field`], so that a [`SlotWriter`] can write it in
+/// place. Nested fields are separated by dots, just like in [`core::mem::offset_of`].
+/// ```rust
+/// # use sling::*;
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Level {
+///     price: u64,
+///     size: u64,
+/// }
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Book {
+///     best: Level,
+///     depth: [Level; 8],
+/// }
+///
+/// unsafe impl NoUninit for Level {}
+/// unsafe impl NoUninit for Book {}
+///
+/// let buffer: RingBuffer<Book, 16> = RingBuffer::new();
+///
+/// let mut writer = buffer.try_lock().unwrap();
+/// let reader = buffer.reader();
+///
+/// writer.push_with(|slot| {
+///     slot.set(field!(Book, best.price), 100);
+///     slot.set(field!(Book, depth).at(2), Level { price: 98, size: 3 });
+/// });
+///
+/// let book = reader.pop_front().unwrap();
+/// assert_eq!((book.best.price, book.depth[2].size), (100, 3));
+/// ```
+/// The value has to have the type of the field.
+/// ```rust,compile_fail
+/// # use sling::*;
+/// let buffer: RingBuffer<[u64; 4], 16> = RingBuffer::new();
+///
+/// let mut writer = buffer.try_lock().unwrap();
+/// writer.push_with(|slot| slot.set(Field::message().at(0), 1u8));
+/// ```
+#[macro_export]
+macro_rules! field {
+    ($message:ty, $($field:tt).+) => {
+        // # Safety: The offset and the type are both taken from the same field of the message.
+        unsafe {
+            $crate::Field::<$message, _>::project(
+                ::core::mem::offset_of!($message, $($field).+),
+                |message: &$message| &message.$($field).+,
+            )
+        }
+    };
+}
+
+impl<T: NoUninit, const N: usize> Ring<T> for RingBuffer<T, N> {
+    #[inline]
+    fn version(&self) -> &AtomicUsize {
+        &self.version
+    }
+
+    #[inline]
+    fn index(&self) -> &AtomicUsize {
+        &self.index
+    }
+
+    #[inline]
+    fn data(&self) -> &[Block<T>] {
+        &self.data
+    }
+
+    #[cfg(any(feature = "async", feature = "std"))]
+    #[inline]
+    fn notify(&self) {
+        self.wakers.wake_all();
+    }
+}
+
+impl<T: NoUninit, const N: usize> Writer<T> for RingBuffer<T, N> {
+    #[inline]
+    fn locked(&self) -> &AtomicBool {
+        &self.locked
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    fn try_acquire(&self) -> bool {
+        let acquired = !self.locked.swap(true, Ordering::Acquire);
+
+        if acquired {
+            self.holder.acquire();
+        }
+
+        acquired
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    fn release(&self) {
+        self.holder.release();
+
+        // This is `SeqCst` so that either we observe a thread waiting for the lock, or it observes
+        // the lock to be free after registering, see `WaitList::register`.
+        self.locked.store(false, Ordering::SeqCst);
+        self.writers.wake_all();
+    }
+}
+
+/// The seqlock protocol shared by the different layouts of the ring buffer. Implementors only
+/// provide access to their state, the protocol itself lives in the provided methods.
+trait Ring<T: NoUninit> {
+    /// The newest block version the writer has started writing.
+    fn version(&self) -> &AtomicUsize;
+
+    /// The index of the next block to be written to.
+    fn index(&self) -> &AtomicUsize;
+
+    /// The blocks holding the messages.
+    fn data(&self) -> &[Block<T>];
+
+    /// Lets waiting readers know that a new message was published.
+    #[inline]
+    fn notify(&self) {}
+
+    /// Increments the sequence at `index` by 1, making it odd, prohibiting reads. Returns the
+    /// sequence of the block before the increment.
+    #[inline]
+    fn start_write(&self, index: usize) -> usize {
+        self.data()[index].start_write()
+    }
+
+    /// Increments the sequence at `index` by 1, making it even and allowing reads.
+    #[inline]
+    fn end_write(&self, index: usize) {
+        self.data()[index].end_write()
+    }
+
+    /// Computes the sequence number of the message written to the block at `index` after `laps`
+    /// messages were written to it before. The lap together with the index uniquely identify
+    /// each message.
+    ///
+    /// The laps are counted in a `u64` next to the message, as the block's `usize` sequence
+    /// wraps around after `2^31` laps on 32-bit targets, see `Block::laps`.
+    #[inline]
+    fn sequence(&self, laps: u64, index: usize) -> u64 {
+        laps.wrapping_mul(self.data().len() as u64)
+            .wrapping_add(index as u64)
+    }
+
+    /// Returns the number of messages the reader whose progress is tracked by `index` and
+    /// `version` has not read yet.
+    fn lag(&self, index: &AtomicUsize, version: &AtomicUsize) -> usize {
+        let len = self.data().len();
+
+        let Some((head, seq)) = self.head() else {
+            return 0;
+        };
+
+        let i = index.load(Ordering::Acquire) % len;
+        let ver = version.load(Ordering::Relaxed);
+
+        // See `missed` for the sequence the reader expects its next block to be at. Both
+        // sequences may have wrapped around, so we only look at how many laps they are apart.
+        let expected = if i == 0 { ver.wrapping_add(2) } else { ver };
+        let laps = seq.wrapping_sub(expected) as isize / 2;
+
+        // Readers may also start out ahead of the writer, see `RingBuffer::reader`.
+        laps.saturating_mul(len as isize)
+            .saturating_add(head as isize + 1 - i as isize)
+            .max(0) as usize
+    }
+
+    /// Returns the number of messages published so far.
+    fn published(&self) -> u64 {
+        let data = self.data();
+
+        loop {
+            let Some(i) = self.newest() else {
+                return 0;
+            };
+            let seq = data[i].seq.load(Ordering::Acquire);
+
+            // Should the writer already be overwriting the block, we look again.
+            if seq & 1 == 0 {
+                if let Some(laps) = data[i].read_laps(seq) {
+                    return self.sequence(laps.wrapping_sub(1), i).wrapping_add(1);
+                }
+            }
+
+            spin_loop();
+        }
+    }
+
+    /// Returns the index of the block holding the newest message and its sequence, or `None` if
+    /// nothing has been published yet.
+    fn head(&self) -> Option<(usize, usize)> {
+        let i = self.newest()?;
+
+        // Should the writer already be overwriting the block, we count the message it replaces.
+        Some((i, self.data()[i].seq.load(Ordering::Acquire) & !1))
+    }
+
+    /// Returns the index of the block holding the newest published message, or `None` if nothing
+    /// has been published yet.
+    fn newest(&self) -> Option<usize> {
+        let data = self.data();
+
+        // The block just before the writer's index holds the newest message.
+        let pos = self.index().load(Ordering::Acquire);
+        let i = (pos % data.len() + data.len() - 1) % data.len();
+
+        // Nothing has been written yet. Blocks' sequences wrap around to zero as well, but only an
+        // empty buffer has the writer at its very start, too. Right after the writer completes
+        // the lap in which they wrap around, the two cannot be told apart.
+        (pos != 0 || data[i].seq.load(Ordering::Acquire) != 0).then_some(i)
+    }
+
+    /// Pops the next element for the reader whose progress is tracked by `index` and `version`,
+    /// alongside its sequence number.
+    ///
+    /// A reader's `index` counts the blocks it has claimed so far, and only ever grows until it
+    /// wraps around, see `advance`; its next block is at `index % N`. Were it to wrap around with
+    /// the blocks instead, a thread that stalled before claiming a block could still claim it a
+    /// lap later, after other threads sharing the reader had moved through every block in the
+    /// meantime.
+    fn try_pop(&self, index: &AtomicUsize, version: &AtomicUsize) -> Result<(u64, T), PopError> {
+        let data = self.data();
+
+        // Checks if data if we are currently caught up.
+        // This is acquire as we want to make sure that we are syncing up the readers version with
+        // the last increment of index. Otherwise we may end up reading old data.
+        let mut pos = index.load(Ordering::Acquire);
+
+        loop {
+            let i = pos % data.len();
+            let ver = version.load(Ordering::Relaxed);
+
+            // Ensures we are not reading old data, or data that is currently being written to.
+            // This is `Acquire` so we observed the write to data should seq1 == seq2.
+            let seq1 = unsafe {
+                check_version(data.get_unchecked(i).seq.load(Ordering::Acquire), ver, i)?
+            };
+
+            // The writer has lapped us, so we move our version up to the one of this block without
+            // advancing the index. The next read then picks up the block in the writer's lap.
+            if let Some(missed) = self.missed(seq1, ver, i) {
+                version
+                    .compare_exchange(ver, resync(seq1, i), Ordering::Relaxed, Ordering::Relaxed)
+                    .map_err(|_| PopError::Contended)?;
+
+                return Err(PopError::Lagged { missed });
+            }
+
+            // On failure we end here, as we have an outdated version and thus are reading consumed
+            // data.
+            version
+                .compare_exchange(ver, seq1, Ordering::Relaxed, Ordering::Relaxed)
+                .map_err(|_| PopError::Contended)?;
+
+            // If this fails, someone has already read the data. This is the only time we should
+            // retry the loop.
+            // This is `Release` on store to ensure that the new version of the `SharedReader` is
+            // observed by all sharing threads, and on failure we `Acquire` to ensure we get the
+            // latest version.
+            if let Err(new) = index.compare_exchange(
+                pos,
+                advance(pos, 1, data.len()),
+                Ordering::Release,
+                Ordering::Acquire,
+            ) {
+                pos = new;
+                continue;
+            }
+
+            // The writer started overwriting the block while we were reading it. We have already
+            // claimed the index, so this message is lost to us.
+            return self.read(i, seq1).ok_or(PopError::Lagged { missed: 1 });
+        }
+    }
+
+    /// Returns the index and version a new reader needs to start out with, so that it reads the
+    /// message at `start` next.
+    fn position(&self, start: Start) -> (usize, usize) {
+        let len = self.data().len();
+
+        // The writer may be publishing a batch, whose messages we count as new ones.
+        let next = match self.latest() {
+            Some((_, _, (seq, _))) => seq.wrapping_add(1),
+            None => 0,
+        };
+
+        let seq = match start {
+            Start::Oldest => next.saturating_sub(len as u64),
+            Start::Newest => next,
+            Start::Seq(seq) => seq,
+        };
+
+        // The block's sequence is `2 * lap + 2` once the message is written, see `missed` for
+        // the version this is expected at.
+        let i = (seq % len as u64) as usize;
+        let lap = (seq / len as u64) as usize;
+
+        // On 32-bit targets the lap is truncated, just like the block's sequence wraps around.
+        let ver = lap.wrapping_mul(2);
+        (i, if i == 0 { ver } else { ver.wrapping_add(2) })
+    }
+
+    /// Reads the next element for the reader whose progress is tracked by `index` and `version`
+    /// without claiming it, alongside its sequence number. Returns the reader's position and
+    /// version the element was read at, and the block's sequence, so that it can be claimed
+    /// later on. Should the writer have lapped the reader, it is moved ahead just like in
+    /// `try_pop`.
+    fn peek(
+        &self,
+        index: &AtomicUsize,
+        version: &AtomicUsize,
+    ) -> Result<(usize, usize, usize, (u64, T)), PopError> {
+        let data = self.data();
+
+        loop {
+            let pos = index.load(Ordering::Acquire);
+            let i = pos % data.len();
+            let ver = version.load(Ordering::Relaxed);
+
+            let seq1 = check_version(data[i].seq.load(Ordering::Acquire), ver, i)?;
+
+            if let Some(missed) = self.missed(seq1, ver, i) {
+                version
+                    .compare_exchange(ver, resync(seq1, i), Ordering::Relaxed, Ordering::Relaxed)
+                    .map_err(|_| PopError::Contended)?;
+
+                return Err(PopError::Lagged { missed });
+            }
+
+            // The writer started overwriting the block while we were reading it. We have not
+            // claimed anything yet, so we simply look again.
+            if let Some(message) = self.read(i, seq1) {
+                return Ok((pos, ver, seq1, message));
+            }
+        }
+    }
+
+    /// Pops the next element for the reader whose progress is tracked by `index` and `version`
+    /// if `f` accepts it, alongside its sequence number. Unlike `try_pop`, the element is read
+    /// before it is claimed, and the claim only succeeds if no other thread sharing the reader
+    /// has moved it in the meantime.
+    fn pop_if(
+        &self,
+        index: &AtomicUsize,
+        version: &AtomicUsize,
+        mut f: impl FnMut(&T) -> bool,
+    ) -> Result<Option<(u64, T)>, PopError> {
+        loop {
+            let (pos, ver, seq1, (seq, message)) = self.peek(index, version)?;
+
+            if !f(&message) {
+                return Ok(None);
+            }
+
+            // See `try_pop` for the orderings.
+            version
+                .compare_exchange(ver, seq1, Ordering::Relaxed, Ordering::Relaxed)
+                .map_err(|_| PopError::Contended)?;
+
+            // Another thread claimed the element first, so we need to look at the next one.
+            if index
+                .compare_exchange(
+                    pos,
+                    advance(pos, 1, self.data().len()),
+                    Ordering::Release,
+                    Ordering::Acquire,
+                )
+                .is_err()
+            {
+                continue;
+            }
+
+            return Ok(Some((seq, message)));
+        }
+    }
+
+    /// Reads the most recently published message and its sequence number, alongside the index
+    /// of its block and the block's sequence.
+    fn latest(&self) -> Option<(usize, usize, (u64, T))> {
+        let data = self.data();
+
+        loop {
+            let i = self.newest()?;
+            let seq = data[i].seq.load(Ordering::Acquire);
+
+            // The writer has moved on and is already overwriting the block, so we look again.
+            if seq & 1 == 1 {
+                spin_loop();
+                continue;
+            }
+
+            if let Some(message) = self.read(i, seq) {
+                return Some((i, seq, message));
+            }
+        }
+    }
+
+    /// Moves the reader whose progress is tracked by `index` and `version` past the most
+    /// recently published message, and returns that message.
+    fn skip_to_latest(&self, index: &AtomicUsize, version: &AtomicUsize) -> Option<T> {
+        let (i, seq, (_, message)) = self.latest()?;
+
+        // This leaves the reader just as if it had popped the message itself. The index moves up
+        // to the block after the message, without ever decreasing, see `try_pop`.
+        let len = self.data().len();
+        version.store(seq, Ordering::Relaxed);
+        let _ = index.fetch_update(Ordering::Release, Ordering::Relaxed, |pos| {
+            Some(advance(pos, (i + 1 + len - pos % len) % len, len))
+        });
+
+        Some(message)
+    }
+
+    /// Reads the message of the block at `i`, whose sequence was `seq` before, alongside its
+    /// sequence number. Returns `None` should the writer have started overwriting the block in
+    /// the meantime.
+    #[inline]
+    fn read(&self, i: usize, seq: usize) -> Option<(u64, T)> {
+        let (laps, message) = self.data()[i].read(seq)?;
+        Some((self.sequence(laps.wrapping_sub(1), i), message))
+    }
+
+    /// Pops up to `buf.len()` consecutive messages for the reader whose progress is tracked by
+    /// `index` and `version`, claiming all of them at once. Returns the number of messages copied
+    /// to the front of `buf`, and the number of messages the writer overran the reader by.
+    fn pop_into(
+        &self,
+        index: &AtomicUsize,
+        version: &AtomicUsize,
+        buf: &mut [T],
+    ) -> (usize, usize) {
+        let mut missed = 0;
+
+        let data = self.data();
+
+        loop {
+            let pos = index.load(Ordering::Acquire);
+            let i = pos % data.len();
+            let ver = version.load(Ordering::Relaxed);
+
+            // We never claim past the end of the buffer, so the version only changes when we start
+            // at the front, just as with single pops.
+            let expected = if i == 0 { ver.wrapping_add(2) } else { ver };
+            let available = buf.len().min(data.len() - i);
+
+            let count = data[i..i + available]
+                .iter()
+                .take_while(|block| block.seq.load(Ordering::Acquire) == expected)
+                .count();
+
+            // The first block is either empty, being written to, or we have been lapped. Single
+            // pops know how to deal with all of these.
+            if count == 0 {
+                if buf.is_empty() {
+                    return (0, missed);
+                }
+
+                match self.try_pop(index, version) {
+                    Ok((_, message)) => {
+                        buf[0] = message;
+                        return (1, missed);
+                    }
+                    Err(PopError::Lagged { missed: m }) => missed += m,
+                    Err(PopError::Contended) => continue,
+                    Err(PopError::Empty) => return (0, missed),
+                }
+            }
+
+            if version
+                .compare_exchange(ver, expected, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+            {
+                continue;
+            }
+
+            // Claims all blocks at once. See `try_pop` for the orderings.
+            if index
+                .compare_exchange(
+                    pos,
+                    advance(pos, count, data.len()),
+                    Ordering::Release,
+                    Ordering::Acquire,
+                )
+                .is_err()
+            {
+                continue;
+            }
+
+            let mut copied = 0;
+
+            for j in i..i + count {
+                match self.read(j, expected) {
+                    Some((_, message)) => {
+                        buf[copied] = message;
+                        copied += 1;
+                    }
+                    None => missed += 1,
+                }
+            }
+
+            return (copied, missed);
+        }
+    }
+
+    /// Returns the number of messages we skipped, should the block at `i` have been written to
+    /// more recently than the next message we expected.
+    #[inline]
+    fn missed(&self, seq: usize, ver: usize, i: usize) -> Option<usize> {
+        // When we are at the start of the buffer, we expect the writer to be one lap ahead of us.
+        let expected = if i == 0 { ver.wrapping_add(2) } else { ver };
+
+        precedes(expected, seq).then(|| seq.wrapping_sub(expected) / 2 * self.data().len())
+    }
+}
+
+/// The single writer's side of the protocol, for layouts whose writer is guarded by a lock.
+trait Writer<T: NoUninit>: Ring<T> {
+    /// Whether a writer currently holds the lock.
+    fn locked(&self) -> &AtomicBool;
+
+    /// Tries to take the writer's lock, returning whether we succeeded.
+    #[inline]
+    fn try_acquire(&self) -> bool {
+        !self.locked().swap(true, Ordering::Acquire)
+    }
+
+    /// Releases the writer's lock.
+    #[inline]
+    fn release(&self) {
+        self.locked().store(false, Ordering::Release);
+    }
+
+    /// Writes a message to the next block, returning its sequence number.
+    #[inline]
+    fn push(&self, val: T) -> u64 {
+        self.push_all(core::iter::once(val)).start
+    }
+
+    /// Writes the messages to consecutive blocks, returning their sequence numbers.
+    #[inline]
+    fn push_all(&self, vals: impl IntoIterator<Item = T>) -> Range<u64> {
+        self.publish(vals.into_iter())
+    }
+
+    /// Lets `f` write a message straight into the next block, returning the message's sequence
+    /// number.
+    #[inline]
+    fn push_with(&self, f: impl FnOnce(&mut SlotWriter<'_, T>)) -> u64 {
+        let start = self.next_sequence();
+        Batch::new(self).write(f);
+        start
+    }
+
+    /// The sequence number the next message is going to be published with.
+    #[inline]
+    fn next_sequence(&self) -> u64 {
+        let index = self.index().load(Ordering::Relaxed);
+
+        // We are the only writer, so the block cannot change under us.
+        self.sequence(self.data()[index].laps(), index)
+    }
+
+    /// Writes the `vals` to consecutive blocks, returning the sequence numbers of the messages
+    /// written. Each block is still locked individually, but the index, the version, and waiting
+    /// readers are only updated once for the whole batch.
+    #[inline]
+    fn publish(&self, vals: impl Iterator<Item = T>) -> Range<u64> {
+        let start = self.next_sequence();
+
+        let mut batch = Batch::new(self);
+        for val in vals {
+            batch.write(|slot| slot.write(val));
+        }
+
+        start..start.wrapping_add(batch.count)
+    }
+}
+
+/// Messages the writer publishes to consecutive blocks. The writer's index and version, and
+/// waiting readers, are only updated once the batch is dropped. This also happens should writing
+/// a message panic, so that no block is left locked.
+struct Batch<'a, T: NoUninit, W: Writer<T> + ?Sized> {
+    writer: &'a W,
+    index: usize,
+    version: usize,
+    count: u64,
+    /// The sequence of the block at `index` before we started writing to it, if we are.
+    writing: Option<usize>,
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T: NoUninit, W: Writer<T> + ?Sized> Batch<'a, T, W> {
+    #[inline]
+    fn new(writer: &'a W) -> Self {
+        Batch {
+            writer,
+            index: writer.index().load(Ordering::Relaxed),
+            version: writer.version().load(Ordering::Relaxed),
+            count: 0,
+            writing: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Lets `f` write the message of the next block.
+    #[inline]
+    fn write(&mut self, f: impl FnOnce(&mut SlotWriter<'_, T>)) {
+        self.writing = Some(self.writer.start_write(self.index));
+        f(&mut SlotWriter {
+            message: &self.writer.data()[self.index].message,
+        });
+        self.end_write();
+    }
+
+    /// Makes the block we are writing to readable, and moves on to the next one.
+    #[inline]
+    fn end_write(&mut self) {
+        let Some(seq) = self.writing.take() else {
+            return;
+        };
+
+        self.writer.end_write(self.index);
+
+        if precedes(self.version, seq.wrapping_add(2)) {
+            self.version = seq.wrapping_add(2);
+        }
+        self.index = (self.index + 1) % self.writer.data().len();
+        self.count += 1;
+    }
+}
+
+impl<T: NoUninit, W: Writer<T> + ?Sized> Drop for Batch<'_, T, W> {
+    fn drop(&mut self) {
+        // Should writing a message have panicked, it is published just as it was left.
+        self.end_write();
+
+        if self.count > 0 {
+            // Update the global version to be newer than the blocks we wrote.
+            self.writer.version().store(self.version, Ordering::Relaxed);
+            self.writer.index().store(self.index, Ordering::Relaxed);
+            self.writer.notify();
+        }
+    }
+}
+
+/// The ordering with which the writer makes a block readable. Readers waiting for the writer
+/// need this to be `SeqCst`, so that the writer cannot miss their registration.
+#[cfg(not(any(feature = "async", feature = "std")))]
+const PUBLISH: Ordering = Ordering::Release;
+#[cfg(any(feature = "async", feature = "std"))]
+const PUBLISH: Ordering = Ordering::SeqCst;
+
+/// Waits a moment for another thread to make progress. Loom needs to be told, so that it can
+/// schedule the thread we are waiting for.
+#[inline]
+fn spin_loop() {
+    #[cfg(not(loom))]
+    core::hint::spin_loop();
+    #[cfg(loom)]
+    loom::thread::yield_now();
+}
+
+/// Returns the version a lagging reader at `i` needs to move to, in order to read the block with
+/// sequence `seq` next.
+#[inline]
+fn resync(seq: usize, i: usize) -> usize {
+    // At the start of the buffer, we expect the block to be one lap ahead of us.
+    if i == 0 {
+        seq.wrapping_sub(2)
+    } else {
+        seq
+    }
+}
+
+/// Whether the sequence `a` is older than `b`. Sequences wrap around once they reach
+/// `usize::MAX`, which on 32-bit targets a busy block does within hours, so rather than comparing
+/// them directly, we look at their distance. This holds as long as the two are less than half the
+/// range of `usize` apart.
+#[inline]
+fn precedes(a: usize, b: usize) -> bool {
+    (a.wrapping_sub(b) as isize) < 0
+}
+
+/// Moves a reader's index ahead by `count` blocks of a buffer of `len` blocks. The index wraps
+/// around at the largest multiple of `len` rather than at `usize::MAX`, so that `index % len`
+/// keeps going through the blocks in order.
+#[inline]
+fn advance(pos: usize, count: usize, len: usize) -> usize {
+    let end = usize::MAX - usize::MAX % len;
+
+    match end - pos {
+        rest if rest <= count => count - rest,
+        _ => pos + count,
+    }
+}
+
+/// Checks if we are reading data we have already consumed.
+#[inline]
+fn check_version(mut seq: usize, ver: usize, i: usize) -> Result<usize, PopError> {
+    // The current version of the
+    if seq & 1 != 0 {
+        return Err(PopError::Empty);
+    }
+
+    // TODO(emilHof) This should not be needed!
+    seq &= usize::MAX - 1;
+
+    if (i == 0 && seq == ver) || precedes(seq, ver) {
+        return Err(PopError::Empty);
+    }
+
+    Ok(seq)
+}
+
+#[repr(C)]
+struct Block<T: NoUninit> {
+    seq: AtomicUsize,
+    /// The number of messages written to the block so far, which the sequence cannot tell once
+    /// it wraps around. It is guarded by the sequence, just like the message.
+    laps: Message<u64>,
+    message: Message<T>,
+}
+
+impl<T: NoUninit> Block<T> {
+    /// Increments the sequence by 1, making it odd, prohibiting reads. Returns the sequence
+    /// before the increment.
+    #[inline]
+    fn start_write(&self) -> usize {
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+
+        // Make sure the state is consistent.
+        assert!(seq & 1 == 0);
+
+        // Readers that observe any of the following stores to the message are guaranteed to
+        // observe the odd sequence as well, see `Block::read`.
+        fence(Ordering::Release);
+
+        seq
+    }
+
+    /// Counts the message written, and increments the sequence by 1, making it even and allowing
+    /// reads.
+    #[inline]
+    fn end_write(&self) {
+        self.laps.store(self.laps().wrapping_add(1));
+
+        let seq = self.seq.fetch_add(1, PUBLISH);
+
+        // Ensure a consistent state.
+        assert!(seq & 1 == 1);
+    }
+
+    /// Writes `val` to the message. Must only be called between [`Block::start_write`] and
+    /// [`Block::end_write`].
+    #[inline]
+    fn write(&self, val: T) {
+        self.message.store(val);
+    }
+
+    /// Copies the message without checking the sequence. This is only consistent for the
+    /// writer.
+    #[inline]
+    fn load(&self) -> MaybeUninit<T> {
+        self.message.load()
+    }
+
+    /// Returns the number of messages written to the block so far, without checking the
+    /// sequence. This is only consistent for the writer.
+    #[inline]
+    fn laps(&self) -> u64 {
+        // # Safety: Blocks start out zeroed.
+        unsafe { self.laps.load().assume_init() }
+    }
+
+    /// Reads the message, whose sequence was `seq` before, alongside the number of messages
+    /// written to the block up to and including it. Returns `None` should the writer have
+    /// started overwriting it in the meantime.
+    #[inline]
+    fn read(&self, seq: usize) -> Option<(u64, T)> {
+        // The writer may be overwriting the message while we copy it, which is fine, as we copy
+        // it atomically, and discard the copy unless the sequence is unchanged afterwards.
+        let laps = self.laps.load();
+        let message = self.load();
+
+        if !self.unchanged(seq) {
+            return None;
+        }
+
+        // # Safety: The writer always initializes the message before publishing it.
+        Some(unsafe { (laps.assume_init(), message.assume_init()) })
+    }
+
+    /// Reads the number of messages written to the block, whose sequence was `seq` before. See
+    /// [`Block::read`].
+    #[inline]
+    fn read_laps(&self, seq: usize) -> Option<u64> {
+        let laps = self.laps.load();
+
+        if !self.unchanged(seq) {
+            return None;
+        }
+
+        // # Safety: Blocks start out zeroed.
+        Some(unsafe { laps.assume_init() })
+    }
+
+    /// Whether the block's sequence is still at `seq` after we copied from it.
+    #[inline]
+    fn unchanged(&self, seq: usize) -> bool {
+        // Make sure the copy happens before we check the sequence again. Should it have seen
+        // any of the writer's stores, we see the odd sequence that preceded them.
+        fence(Ordering::Acquire);
+
+        seq == self.seq.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: NoUninit> Debug for Block<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Block")
+            .field("seq", &self.seq.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Aligns its contents to the cache line.
+#[cfg_attr(
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "powerpc64",
+    ),
+    repr(align(128))
+)]
+#[cfg_attr(
     any(
         target_arch = "arm",
         target_arch = "mips",
@@ -525,7 +2073,7 @@ mod test {
         let mut writer = buffer.try_lock().unwrap();
 
         for i in 0..32 {
-            writer.push_back(i)
+            writer.push_back(i);
         }
 
         println!("buffer: {buffer:?}");
@@ -549,6 +2097,58 @@ mod test {
         assert!(buffer.try_lock().is_err());
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_lock_holder() {
+        let buffer = RingBuffer::<(), 32>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let thread = std::thread::current().id();
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let err = buffer.try_lock().unwrap_err();
+                assert_eq!(
+                    err.holder().map(|h| (h.thread(), h.tag())),
+                    Some((thread, None))
+                );
+            });
+        });
+
+        writer.set_tag("primary");
+
+        let err = buffer.try_lock().unwrap_err();
+        assert_eq!(err.holder().unwrap().tag(), Some("primary"));
+        assert_eq!(
+            std::format!("{err}"),
+            std::format!("the writer's lock is held by another writer (primary on {thread:?})")
+        );
+
+        drop(writer);
+
+        // A new writer does not inherit the tag.
+        let _writer = buffer.try_lock().unwrap();
+        assert_eq!(buffer.try_lock().unwrap_err().holder().unwrap().tag(), None);
+    }
+
+    #[test]
+    fn test_lock_spin() {
+        let buffer = RingBuffer::<_, 32>::new();
+
+        let writer = buffer.try_lock().unwrap();
+
+        std::thread::scope(|s| {
+            let handle = s.spawn(|| buffer.lock_spin().push_back(1));
+
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            drop(writer);
+
+            assert_eq!(handle.join().unwrap(), 0);
+        });
+
+        assert_eq!(buffer.latest(), Some(1));
+    }
+
     #[test]
     fn test_read() {
         let buffer = RingBuffer::<_, 32>::new();
@@ -556,7 +2156,7 @@ mod test {
         let mut writer = buffer.try_lock().unwrap();
 
         for i in 0..32 {
-            writer.push_back(i)
+            writer.push_back(i);
         }
 
         let reader = buffer.reader();
@@ -566,6 +2166,515 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_lagged() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        writer.push_back(0);
+        writer.push_back(1);
+
+        assert_eq!(reader.try_pop_front(), Ok(0));
+
+        for i in 2..7 {
+            writer.push_back(i);
+        }
+
+        assert_eq!(reader.try_pop_front(), Err(PopError::Lagged { missed: 4 }));
+        assert_eq!(reader.try_pop_front(), Ok(5));
+        assert_eq!(reader.try_pop_front(), Ok(6));
+        assert_eq!(reader.try_pop_front(), Err(PopError::Empty));
+    }
+
+    #[test]
+    fn test_sequence() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        for i in 0..10 {
+            assert_eq!(writer.push_back(i), i);
+        }
+
+        assert_eq!(reader.pop_front_with_seq(), Some((8, 8)));
+        assert_eq!(reader.pop_front_with_seq(), Some((9, 9)));
+        assert_eq!(reader.pop_front_with_seq(), None);
+
+        for i in 10..14 {
+            assert_eq!(writer.push_back(i), i);
+        }
+
+        for i in 10..14 {
+            assert_eq!(reader.pop_front_with_seq(), Some((i, i)));
+        }
+    }
+
+    #[test]
+    fn test_index_keeps_counting() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        writer.extend(0..4);
+        for i in 0..4 {
+            assert_eq!(reader.pop_front(), Some(i));
+        }
+
+        // A thread sharing the reader that stalled before claiming the first block would still
+        // try to claim index 0, which must fail now that the others have moved a lap ahead.
+        assert_eq!(reader.index.load(Ordering::Relaxed), 4);
+
+        writer.extend(4..8);
+        let mut buf = [0; 4];
+        assert_eq!(reader.pop_into(&mut buf), 4);
+
+        writer.extend(8..10);
+        assert_eq!(reader.skip_to_latest(), Some(9));
+
+        // Neither batched pops nor skipping ahead move the index back to an earlier lap.
+        assert_eq!(reader.index.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn test_push_slice() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        assert_eq!(writer.push_slice(&[]), 0..0);
+        assert_eq!(writer.push_slice(&[0, 1, 2]), 0..3);
+        assert_eq!(reader.pop_front_with_seq(), Some((0, 0)));
+
+        assert_eq!(writer.push_slice(&[3, 4, 5, 6, 7, 8]), 3..9);
+        assert_eq!(reader.try_pop_front(), Err(PopError::Lagged { missed: 4 }));
+
+        for i in 5..9 {
+            assert_eq!(reader.pop_front_with_seq(), Some((i, i)));
+        }
+        assert_eq!(reader.pop_front(), None);
+
+        writer.extend([9, 10]);
+        assert_eq!(reader.pop_front_with_seq(), Some((9, 9)));
+        assert_eq!(reader.pop_front_with_seq(), Some((10, 10)));
+        assert_eq!(writer.push_back(11), 11);
+    }
+
+    #[test]
+    fn test_push_slice_multi_reader() {
+        let buffer = RingBuffer::<_, 64>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let reader = buffer.reader();
+                s.spawn(move || {
+                    let mut last = None;
+                    while last != Some(999) {
+                        if let Some((seq, val)) = reader.pop_front_with_seq() {
+                            assert_eq!(seq, val);
+                            assert!(last < Some(seq));
+                            last = Some(seq);
+                        }
+                    }
+                });
+            }
+
+            let vals: std::vec::Vec<u64> = (0..1000).collect();
+            for chunk in vals.chunks(10) {
+                writer.push_slice(chunk);
+                std::thread::yield_now();
+            }
+        });
+    }
+
+    #[test]
+    fn test_pop_into() {
+        let buffer = RingBuffer::<_, 8>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        let mut buf = [0; 4];
+        assert_eq!(reader.pop_into(&mut buf), 0);
+        assert_eq!(reader.pop_into(&mut []), 0);
+
+        writer.push_slice(&[0, 1, 2, 3, 4, 5]);
+        assert_eq!(reader.pop_into(&mut buf), 4);
+        assert_eq!(buf, [0, 1, 2, 3]);
+
+        // We never claim past the end of the buffer.
+        writer.push_slice(&[6, 7, 8, 9]);
+        assert_eq!(reader.pop_into(&mut buf), 4);
+        assert_eq!(buf, [4, 5, 6, 7]);
+        assert_eq!(reader.pop_into(&mut buf), 2);
+        assert_eq!(buf[..2], [8, 9]);
+
+        // Being lapped skips ahead, just like single pops.
+        writer.push_slice(&[10, 11, 12, 13, 14, 15, 16, 17, 18, 19]);
+        assert_eq!(reader.pop_into(&mut buf), 2);
+        assert_eq!(buf[..2], [18, 19]);
+        assert_eq!(reader.pop_into(&mut buf), 0);
+    }
+
+    #[test]
+    fn test_pop_into_multi_reader() {
+        let buffer = RingBuffer::<_, 1024>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+        let read = AtomicUsize::new(0);
+
+        std::thread::scope(|s| {
+            let reader = &reader;
+            let read = &read;
+            let handles: std::vec::Vec<_> = (0..4)
+                .map(|_| {
+                    s.spawn(move || {
+                        let mut sum = 0;
+                        let mut buf = [0; 16];
+                        while read.load(Ordering::Relaxed) < 1000 {
+                            let n = reader.pop_into(&mut buf);
+                            read.fetch_add(n, Ordering::Relaxed);
+                            sum += buf[..n].iter().sum::<u64>();
+                        }
+                        sum
+                    })
+                })
+                .collect();
+
+            for i in 0..1000 {
+                writer.push_back(i);
+            }
+
+            let sum: u64 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+            assert_eq!(sum, (0..1000).sum::<u64>());
+        });
+    }
+
+    #[test]
+    fn test_push_with() {
+        let buffer = RingBuffer::<[u32; 64], 2>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        for i in 0..3 {
+            let seq = writer.push_with(|slot| slot.write([i; 64]));
+            assert_eq!(seq, i as u64);
+        }
+
+        // The slot still holds the message from the previous lap.
+        writer.push_with(|slot| {
+            slot.set(Field::message().at(0), 9);
+            slot.set_slice(Field::message(), 61, &[7, 8, 9]);
+        });
+
+        assert_eq!(reader.pop_front(), Some([2; 64]));
+
+        let mut expected = [1; 64];
+        expected[0] = 9;
+        expected[61..].copy_from_slice(&[7, 8, 9]);
+        assert_eq!(reader.pop_front(), Some(expected));
+        assert_eq!(reader.pop_front(), None);
+
+        // A panicking writer still publishes the message, so that the block does not stay locked.
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            writer.push_with(|slot| {
+                slot.write([5; 64]);
+                panic!("the writer panicked");
+            })
+        }));
+        assert!(res.is_err());
+
+        assert_eq!(writer.push_back([6; 64]), 5);
+        assert_eq!(reader.pop_front(), Some([5; 64]));
+        assert_eq!(reader.pop_front(), Some([6; 64]));
+    }
+
+    #[test]
+    fn test_reader_from() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+
+        let oldest = buffer.reader_from(Start::Oldest);
+        let newest = buffer.reader_from(Start::Newest);
+        assert_eq!(oldest.pop_front(), None);
+        assert_eq!(newest.pop_front(), None);
+
+        writer.extend(0..6);
+
+        // Both started out at the very first message, which has been overwritten since.
+        assert_eq!(oldest.pop_front_with_seq(), Some((4, 4)));
+        assert_eq!(newest.pop_front_with_seq(), Some((4, 4)));
+
+        assert!(buffer.reader_from(Start::Oldest).drain().eq(2..6));
+        assert_eq!(buffer.reader_from(Start::Newest).drain().next(), None);
+        assert!(buffer.reader_from(Start::Seq(3)).drain().eq(3..6));
+        assert!(buffer.reader_from(Start::Seq(4)).drain().eq(4..6));
+
+        // Messages that were overwritten already are skipped.
+        assert!(buffer.reader_from(Start::Seq(0)).drain().eq(4..6));
+
+        // Readers starting in the future wait for the writer to get there.
+        let future = buffer.reader_from(Start::Seq(9));
+        writer.extend(6..9);
+        assert_eq!(future.pop_front(), None);
+        writer.extend(9..11);
+        assert!(future.drain().eq(9..11));
+    }
+
+    #[test]
+    fn test_lag_and_missed() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        assert_eq!(buffer.published(), 0);
+        assert_eq!(reader.lag(), 0);
+
+        writer.extend(0..3);
+        assert_eq!(buffer.published(), 3);
+        assert_eq!(reader.lag(), 3);
+        assert_eq!(reader.pop_front(), Some(0));
+        assert_eq!(reader.lag(), 2);
+
+        // Readers created mid-lap start out at the next lap, so they are not behind yet.
+        assert_eq!(buffer.reader().lag(), 0);
+
+        writer.extend(3..9);
+        assert_eq!(buffer.published(), 9);
+        assert_eq!(reader.lag(), 8);
+        assert_eq!(reader.missed(), 0);
+
+        assert_eq!(reader.try_pop_front(), Err(PopError::Lagged { missed: 4 }));
+        assert_eq!(reader.missed(), 4);
+        assert_eq!(reader.lag(), 4);
+
+        // Batched pops count overruns just the same, and clones keep the count.
+        writer.extend(9..17);
+        let mut buf = [0; 4];
+        assert_eq!(reader.pop_into(&mut buf), 3);
+        assert_eq!(buf[..3], [13, 14, 15]);
+        assert_eq!(reader.missed(), 12);
+        assert_eq!(reader.clone().missed(), 12);
+
+        assert_eq!(reader.pop_front(), Some(16));
+        assert_eq!(reader.lag(), 0);
+        assert_eq!(reader.missed(), 12);
+    }
+
+    #[test]
+    fn test_precedes() {
+        assert!(precedes(0, 2));
+        assert!(precedes(usize::MAX - 1, 0));
+        assert!(!precedes(0, usize::MAX - 1));
+        assert!(!precedes(2, 2));
+
+        // A block that wrapped around is newer than the version it was written after.
+        assert_eq!(check_version(0, usize::MAX - 1, 1), Ok(0));
+        assert_eq!(check_version(usize::MAX - 1, 0, 1), Err(PopError::Empty));
+    }
+
+    #[test]
+    fn test_sequence_wraparound() {
+        let buffer = RingBuffer::<u64, 4>::new();
+
+        // The blocks and the version start out just short of wrapping around, rather than at 0.
+        for block in &buffer.data {
+            block.seq.store(usize::MAX - 3, Ordering::Relaxed);
+        }
+        buffer.version.store(usize::MAX - 3, Ordering::Relaxed);
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+        let mut subscriber = buffer.subscriber();
+
+        // The blocks' sequences wrap around in the second lap.
+        for i in 0..10 {
+            writer.extend(i * 3..i * 3 + 3);
+            assert_eq!(reader.lag(), 3);
+
+            for j in i * 3..i * 3 + 3 {
+                assert_eq!(reader.pop_front(), Some(j));
+                assert_eq!(subscriber.pop_front(), Some(j));
+            }
+
+            assert_eq!(reader.pop_front(), None);
+            assert_eq!(subscriber.pop_front(), None);
+        }
+
+        // Overruns are still noticed, rather than mistaken for stale blocks.
+        writer.extend(30..36);
+        assert_eq!(reader.try_pop_front(), Err(PopError::Lagged { missed: 4 }));
+        assert_eq!(
+            subscriber.try_pop_front(),
+            Err(PopError::Lagged { missed: 4 })
+        );
+        assert!(reader.drain().eq(34..36));
+        assert!(core::iter::from_fn(|| subscriber.pop_front()).eq(34..36));
+    }
+
+    #[test]
+    fn test_sequence_numbers_wraparound() {
+        let buffer = RingBuffer::<u64, 4>::new();
+
+        // The blocks have been written to more often than their sequences can count on 32-bit
+        // targets, where they are about to wrap around for the second time.
+        let laps: u64 = (1 << 33) - 2;
+        let seq = (laps * 2) as usize;
+        for block in &buffer.data {
+            block.seq.store(seq, Ordering::Relaxed);
+            block.laps.store(laps);
+        }
+        buffer.version.store(seq, Ordering::Relaxed);
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        // Sequence numbers keep counting up as the blocks' sequences wrap around.
+        let first = laps * 4;
+        for i in 0..12 {
+            assert_eq!(writer.push_back(i), first + i);
+            assert_eq!(reader.pop_front_with_seq(), Some((first + i, i)));
+        }
+
+        assert_eq!(buffer.published(), first + 12);
+        assert_eq!(buffer.reader_from(Start::Oldest).pop_front(), Some(8));
+        assert_eq!(
+            buffer.reader_from(Start::Seq(first + 10)).pop_front(),
+            Some(10)
+        );
+
+        let newest = buffer.reader_from(Start::Newest);
+        assert_eq!(writer.push_back(12), first + 12);
+        assert_eq!(newest.pop_front_with_seq(), Some((first + 12, 12)));
+    }
+
+    #[test]
+    fn test_index_wraparound() {
+        let buffer = RingBuffer::<u64, 3>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        // The reader's index is about to wrap around, and `usize::MAX` is no multiple of 3.
+        let end = usize::MAX - usize::MAX % 3;
+        reader.index.store(end - 3, Ordering::Relaxed);
+
+        for i in 0..4 {
+            writer.extend(i * 2..i * 2 + 2);
+            assert_eq!(reader.pop_front(), Some(i * 2));
+
+            let mut buf = [0; 2];
+            assert_eq!(reader.pop_into(&mut buf), 1);
+            assert_eq!(buf[0], i * 2 + 1);
+        }
+
+        assert!(reader.index.load(Ordering::Relaxed) < end - 3);
+    }
+
+    #[test]
+    fn test_peek() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        assert_eq!(reader.peek_front(), None);
+        assert_eq!(reader.pop_if(|_| true), None);
+
+        // Peeking a lagging reader skips ahead, just like popping does.
+        writer.extend(0..6);
+        assert_eq!(reader.peek_front(), Some(4));
+        assert_eq!(reader.peek_front(), Some(4));
+        assert_eq!(reader.pop_if(|&val| val == 5), None);
+        assert_eq!(reader.pop_if(|&val| val == 4), Some(4));
+        assert_eq!(reader.pop_front(), Some(5));
+    }
+
+    #[test]
+    fn test_pop_if_shared() {
+        use std::vec::Vec;
+
+        let buffer = RingBuffer::<_, 256>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        writer.extend(0..200u32);
+
+        // Threads only pop the elements they looked at, so the parities never mix up.
+        let (even, odd): (Vec<_>, Vec<_>) = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4u32)
+                .map(|t| {
+                    let reader = &reader;
+                    s.spawn(move || {
+                        let mut read = Vec::new();
+                        while let Some(val) = reader.peek_front() {
+                            if let Some(val) = reader.pop_if(|&val| val % 2 == t % 2) {
+                                read.push((t, val));
+                            } else if val % 2 != t % 2 {
+                                std::thread::yield_now();
+                            }
+                        }
+                        read
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .partition(|(t, _)| t % 2 == 0)
+        });
+
+        assert!(even.iter().all(|(_, val)| val % 2 == 0));
+        assert!(odd.iter().all(|(_, val)| val % 2 == 1));
+
+        let mut read: Vec<_> = even.into_iter().chain(odd).map(|(_, val)| val).collect();
+        read.sort();
+        assert_eq!(read, (0..200).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_latest() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        assert_eq!(buffer.latest(), None);
+        assert_eq!(reader.skip_to_latest(), None);
+
+        // Wrap around, so that the newest message sits in the last block.
+        for i in 0..8 {
+            writer.push_back(i);
+            assert_eq!(buffer.latest(), Some(i));
+        }
+
+        assert_eq!(reader.skip_to_latest(), Some(7));
+        assert_eq!(reader.try_pop_front(), Err(PopError::Empty));
+
+        writer.push_slice(&[8, 9]);
+
+        assert_eq!(reader.pop_front_with_seq(), Some((8, 8)));
+        assert_eq!(reader.skip_to_latest(), Some(9));
+        assert_eq!(reader.pop_front(), None);
+
+        let mut subscriber = buffer.subscriber();
+        assert_eq!(subscriber.skip_to_latest(), Some(9));
+        writer.push_back(10);
+        assert_eq!(subscriber.pop_front(), Some(10));
+    }
+
     #[test]
     fn test_multi_reader() {
         let buffer = RingBuffer::<_, 128>::new();
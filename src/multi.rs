@@ -0,0 +1,470 @@
+//! A ring buffer that several producers can publish into at once.
+
+#[cfg(not(loom))]
+use core::mem::MaybeUninit;
+use core::ops::Range;
+#[cfg(not(loom))]
+use core::ptr::write_bytes;
+
+use crate::{
+    advance, precedes, spin_loop, AtomicUsize, Block, NoUninit, Ordering, Padded, Reader, Ring,
+    Start,
+};
+
+/// A fixed-size, non-write-blocking, ring buffer, that behaves like a MPMC queue and can be
+/// safely shared across threads. Unlike a [`RingBuffer`](crate::RingBuffer), it does not need to
+/// be locked for writing: any number of [`MultiWriter`]s publish into it concurrently.
+///
+/// Producers claim their blocks in turn, and readers consume the messages in the order in which
+/// their blocks were claimed. A producer that stalls while writing its message therefore holds up
+/// readers until it is done, and producers that lap it wait for it, too.
+#[derive(Debug)]
+pub struct MultiRingBuffer<T: NoUninit, const N: usize> {
+    version: Padded<AtomicUsize>,
+    /// The number of blocks claimed by producers so far, which only ever grows until it wraps
+    /// around at `N * LAPS`, see `publish`.
+    index: Padded<AtomicUsize>,
+    data: [Block<T>; N],
+}
+
+impl<T: NoUninit, const N: usize> Default for MultiRingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<T: NoUninit, const N: usize> Send for MultiRingBuffer<T, N> {}
+unsafe impl<T: NoUninit, const N: usize> Sync for MultiRingBuffer<T, N> {}
+
+impl<T: NoUninit, const N: usize> MultiRingBuffer<T, N> {
+    /// The number of laps after which the claimed blocks wrap around, which is the largest power
+    /// of two that still leaves room for `N` blocks per lap.
+    const LAPS: usize = 1 << (usize::MAX / N).ilog2();
+
+    /// Constructs a new, empty array with a fixed length.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: MultiRingBuffer<[u8; 16], 1024> = MultiRingBuffer::new();
+    /// ```
+    #[cfg(not(loom))]
+    pub fn new() -> MultiRingBuffer<T, N> {
+        // Initialize the array.
+        let data: [Block<T>; N] = unsafe {
+            let mut data: [MaybeUninit<Block<T>>; N] = MaybeUninit::uninit().assume_init();
+            write_bytes(&mut data, 0, 1);
+
+            // See `RingBuffer::new`.
+            core::ptr::read((&data as *const [MaybeUninit<Block<T>>; N]).cast::<[Block<T>; N]>())
+        };
+
+        MultiRingBuffer {
+            version: Padded(AtomicUsize::new(0)),
+            index: Padded(AtomicUsize::new(0)),
+            data,
+        }
+    }
+
+    /// Loom has special types that need to be initialized differently.
+    #[cfg(loom)]
+    pub fn new() -> MultiRingBuffer<T, N> {
+        MultiRingBuffer {
+            version: Padded(AtomicUsize::new(0)),
+            index: Padded(AtomicUsize::new(0)),
+            data: core::array::from_fn(|_| Block {
+                seq: AtomicUsize::new(0),
+                laps: crate::Message::zeroed(),
+                message: crate::Message::zeroed(),
+            }),
+        }
+    }
+
+    /// Creates a new [`MultiWriter`], which publishes into the queue alongside every other
+    /// [`MultiWriter`].
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: MultiRingBuffer<u32, 1024> = MultiRingBuffer::new();
+    ///
+    /// let reader = buffer.reader();
+    ///
+    /// std::thread::scope(|s| {
+    ///     for t in 0..4 {
+    ///         let writer = buffer.writer();
+    ///         s.spawn(move || writer.push_back(t));
+    ///     }
+    /// });
+    ///
+    /// let mut read: Vec<_> = std::iter::from_fn(|| reader.pop_front()).collect();
+    /// read.sort();
+    /// assert_eq!(read, [0, 1, 2, 3]);
+    /// ```
+    #[inline]
+    pub fn writer(&self) -> MultiWriter<'_, T, N> {
+        MultiWriter { buffer: self }
+    }
+
+    /// Creates a new [`MultiReader`] which provides shared read access of the queue, just like a
+    /// [`SharedReader`](crate::SharedReader) does for a [`RingBuffer`](crate::RingBuffer).
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: MultiRingBuffer<[u8; 16], 1024> = MultiRingBuffer::new();
+    ///
+    /// let reader = buffer.reader();
+    /// ```
+    #[inline]
+    pub fn reader(&self) -> MultiReader<'_, T, N> {
+        Reader::new(self, 0, self.version.load(Ordering::Relaxed))
+    }
+
+    /// Creates a new [`MultiReader`] that starts reading at `start`. See
+    /// [`RingBuffer::reader_from`](crate::RingBuffer::reader_from).
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: MultiRingBuffer<u32, 4> = MultiRingBuffer::new();
+    ///
+    /// buffer.writer().push_slice(&[1, 2, 3]);
+    ///
+    /// assert_eq!(buffer.reader_from(Start::Newest).pop_front(), None);
+    /// ```
+    #[inline]
+    pub fn reader_from(&self, start: Start) -> MultiReader<'_, T, N> {
+        let (index, version) = self.position(start);
+        Reader::new(self, index, version)
+    }
+
+    /// Claims `vals.len()` consecutive blocks and writes the `vals` to them, returning the
+    /// sequence numbers of the messages written.
+    fn publish(&self, vals: &[T]) -> Range<u64> {
+        let end = N * Self::LAPS;
+
+        // Our tickets wrap around at a multiple of `N`, so that `ticket % N` keeps going through
+        // the blocks in order. The update never fails.
+        let mut ticket = self
+            .index
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |pos| {
+                Some(advance(pos, vals.len(), end))
+            })
+            .unwrap_or_else(|pos| pos);
+
+        let mut first = None;
+
+        for &val in vals {
+            let i = ticket % N;
+            let block = &self.data[i];
+            let lap = ticket / N;
+
+            // The block is ours once the producer of the previous lap has published it, which
+            // leaves it at our lap. Only the lap modulo `LAPS` is left in our ticket, and as
+            // `LAPS` divides the laps a block's sequence wraps around at, it is all we need to
+            // compare, as long as fewer than `LAPS` producers are waiting for the same block.
+            // This is `Acquire` so our write is ordered after theirs.
+            let seq = loop {
+                let seq = block.seq.load(Ordering::Acquire);
+                if seq & 1 == 0 && (seq / 2).wrapping_sub(lap).is_multiple_of(Self::LAPS) {
+                    break seq;
+                }
+                spin_loop();
+            };
+
+            // The block is ours now, so its laps cannot change under us.
+            first.get_or_insert(self.sequence(block.laps(), i));
+
+            block.start_write();
+            block.write(val);
+            block.end_write();
+
+            // Update the global version to be at least as new as the block we wrote. Versions wrap
+            // around, so this cannot be a plain `fetch_max`.
+            let ver = seq.wrapping_add(2);
+            let _ = self
+                .version
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+                    precedes(old, ver).then_some(ver)
+                });
+
+            ticket = advance(ticket, 1, end);
+        }
+
+        // The sequence number of a message is the number of blocks claimed before it.
+        let first = first.unwrap_or_else(|| self.published());
+        first..first.wrapping_add(vals.len() as u64)
+    }
+}
+
+impl<T: NoUninit, const N: usize> Ring<T> for MultiRingBuffer<T, N> {
+    #[inline]
+    fn version(&self) -> &AtomicUsize {
+        &self.version
+    }
+
+    #[inline]
+    fn index(&self) -> &AtomicUsize {
+        &self.index
+    }
+
+    #[inline]
+    fn data(&self) -> &[Block<T>] {
+        &self.data
+    }
+
+    /// The block just before the index may have been claimed by a producer that has yet to write
+    /// to it, so we look back through the claimed blocks for the newest one that holds the
+    /// message of its ticket already, see `publish`.
+    fn newest(&self) -> Option<usize> {
+        let end = N * Self::LAPS;
+        let mut ticket = self.index.load(Ordering::Acquire);
+
+        // Should none of the last `N` tickets be published yet, producers have stalled on every
+        // single block, and we rather report nothing than a message a lap old.
+        for _ in 0..N {
+            ticket = ticket.checked_sub(1).unwrap_or(end - 1);
+
+            let i = ticket % N;
+            let lap = ticket / N;
+
+            // Blocks are at zero before they are first written to, as well as once their
+            // sequence wraps around, see `Ring::newest`.
+            let seq = self.data[i].seq.load(Ordering::Acquire);
+            if seq != 0
+                && seq & 1 == 0
+                && (seq / 2).wrapping_sub(lap + 1).is_multiple_of(Self::LAPS)
+            {
+                return Some(i);
+            }
+        }
+
+        None
+    }
+}
+
+/// A [`Reader`] borrowing its [`MultiRingBuffer`].
+pub type MultiReader<'read, T, const N: usize> = Reader<T, &'read MultiRingBuffer<T, N>>;
+
+/// Provides shared write access to the [`MultiRingBuffer`]. Any number of [`MultiWriter`]s may
+/// publish concurrently.
+#[derive(Debug, Clone, Copy)]
+pub struct MultiWriter<'write, T: NoUninit, const N: usize> {
+    buffer: &'write MultiRingBuffer<T, N>,
+}
+
+unsafe impl<'write, T: NoUninit, const N: usize> Send for MultiWriter<'write, T, N> {}
+unsafe impl<'write, T: NoUninit, const N: usize> Sync for MultiWriter<'write, T, N> {}
+
+impl<'write, T: NoUninit, const N: usize> MultiWriter<'write, T, N> {
+    /// Push a new value to the back of the queue. Returns the sequence number of the message,
+    /// which counts the messages pushed to the [`MultiRingBuffer`] by all producers. This only
+    /// waits should a producer a whole lap behind us still be writing to our block.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: MultiRingBuffer<[u8; 3], 1024> = MultiRingBuffer::new();
+    ///
+    /// let first = buffer.writer();
+    /// let second = buffer.writer();
+    ///
+    /// assert_eq!(first.push_back([12, 21, 04]), 0);
+    /// assert_eq!(second.push_back([12, 21, 04]), 1);
+    /// ```
+    #[inline]
+    pub fn push_back(&self, val: T) -> u64 {
+        self.buffer.publish(core::slice::from_ref(&val)).start
+    }
+
+    /// Pushes all values to the back of the queue in order, returning the range of their
+    /// sequence numbers. Messages of other producers are never interleaved with the values.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: MultiRingBuffer<u8, 1024> = MultiRingBuffer::new();
+    ///
+    /// let writer = buffer.writer();
+    ///
+    /// assert_eq!(writer.push_slice(&[1, 2, 3]), 0..3);
+    /// assert_eq!(writer.push_back(4), 3);
+    /// ```
+    #[inline]
+    pub fn push_slice(&self, vals: &[T]) -> Range<u64> {
+        self.buffer.publish(vals)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_multi_writers() {
+        let buffer = MultiRingBuffer::<_, 1024>::new();
+
+        let reader = buffer.reader();
+
+        std::thread::scope(|s| {
+            for t in 0..4u64 {
+                let writer = buffer.writer();
+                s.spawn(move || {
+                    for i in 0..100 {
+                        writer.push_back([t, i]);
+                    }
+                });
+            }
+        });
+
+        let read: Vec<_> = core::iter::from_fn(|| reader.pop_front_with_seq()).collect();
+        assert_eq!(read.len(), 400);
+
+        // Every producer's messages are read in the order they were pushed.
+        for t in 0..4 {
+            let sent: Vec<_> = read
+                .iter()
+                .filter(|(_, m)| m[0] == t)
+                .map(|(_, m)| m[1])
+                .collect();
+            assert_eq!(sent, (0..100).collect::<Vec<_>>());
+        }
+
+        assert!(read.iter().map(|(seq, _)| *seq).eq(0..400));
+    }
+
+    #[test]
+    fn test_multi_wraparound() {
+        let buffer = MultiRingBuffer::<u64, 4>::new();
+        let laps = MultiRingBuffer::<u64, 4>::LAPS;
+
+        // The producers start out two blocks short of their tickets wrapping around, rather than
+        // at 0.
+        for (i, block) in buffer.data.iter().enumerate() {
+            let lap = if i < 2 { laps } else { laps - 1 };
+            block.seq.store(lap * 2, Ordering::Relaxed);
+            block.laps.store(lap as u64);
+        }
+        buffer.index.store(4 * laps - 2, Ordering::Relaxed);
+        buffer.version.store(laps * 2, Ordering::Relaxed);
+
+        let reader = buffer.reader_from(Start::Newest);
+        let writer = buffer.writer();
+
+        // Sequence numbers keep counting up as the tickets wrap around.
+        let first = (4 * laps - 2) as u64;
+        assert_eq!(writer.push_slice(&[0, 1, 2]), first..first + 3);
+        assert_eq!(writer.push_back(3), first + 3);
+
+        for i in 0..4 {
+            assert_eq!(reader.pop_front_with_seq(), Some((first + i, i)));
+        }
+
+        // Laps later, producers still find their blocks.
+        for i in 4..12 {
+            assert_eq!(writer.push_back(i), first + i);
+            assert_eq!(reader.pop_front(), Some(i));
+        }
+    }
+
+    #[test]
+    fn test_multi_stalled_newest() {
+        let buffer = MultiRingBuffer::<u64, 4>::new();
+
+        let reader = buffer.reader();
+        let writer = buffer.writer();
+        writer.push_slice(&[0, 1, 2, 3]);
+
+        // A producer claims the next block, but stalls before writing to it, which still holds
+        // the message of the previous lap.
+        buffer.index.store(5, Ordering::Relaxed);
+
+        assert_eq!(buffer.published(), 4);
+        assert_eq!(reader.lag(), 4);
+        assert_eq!(buffer.reader_from(Start::Newest).pop_front(), None);
+
+        let latest = buffer.reader();
+        assert_eq!(latest.skip_to_latest(), Some(3));
+        assert_eq!(latest.pop_front(), None);
+
+        buffer.data[0].start_write();
+        buffer.data[0].write(4);
+        buffer.data[0].end_write();
+
+        assert_eq!(buffer.published(), 5);
+        assert_eq!(latest.pop_front(), Some(4));
+        assert_eq!(buffer.reader_from(Start::Newest).pop_front(), None);
+    }
+
+    #[test]
+    fn test_multi_writers_newest() {
+        let buffer = MultiRingBuffer::<u64, 64>::new();
+
+        let tail = buffer.reader();
+        let done = core::sync::atomic::AtomicBool::new(false);
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let mut seen = None;
+                while !done.load(Ordering::Relaxed) {
+                    while let Some((seq, _)) = tail.pop_front_with_seq() {
+                        seen = Some(seq);
+                    }
+
+                    // A reader starting at the newest message never reads one we have seen
+                    // already, even while producers are still writing to the blocks before it.
+                    let newest = buffer.reader_from(Start::Newest);
+                    if let (Some((seq, _)), Some(seen)) = (newest.pop_front_with_seq(), seen) {
+                        assert!(seq > seen, "{seq} was read after {seen}");
+                    }
+                }
+            });
+
+            let writers: Vec<_> = (0..3u64)
+                .map(|t| {
+                    let writer = buffer.writer();
+                    s.spawn(move || {
+                        for i in 0..2000 {
+                            writer.push_slice(&[t * 2000 + i; 3]);
+                        }
+                    })
+                })
+                .collect();
+
+            writers.into_iter().for_each(|h| h.join().unwrap());
+            done.store(true, Ordering::Relaxed);
+        });
+
+        assert_eq!(buffer.published(), 18000);
+    }
+
+    #[test]
+    fn test_multi_writers_lapping() {
+        let buffer = MultiRingBuffer::<[usize; 2], 16>::new();
+
+        let reader = buffer.reader();
+        let done = core::sync::atomic::AtomicBool::new(false);
+
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                let mut last = [0; 4];
+                loop {
+                    match reader.pop_front() {
+                        // Producers overrun the reader, but never reorder their own messages.
+                        Some([t, i]) => {
+                            assert!(last[t] <= i);
+                            last[t] = i;
+                        }
+                        None if done.load(Ordering::Relaxed) => break,
+                        None => {}
+                    }
+                }
+            });
+
+            let writers: Vec<_> = (0..4usize)
+                .map(|t| {
+                    let writer = buffer.writer();
+                    s.spawn(move || {
+                        for i in 0..1000 {
+                            writer.push_slice(&[[t, i]; 3]);
+                        }
+                    })
+                })
+                .collect();
+
+            writers.into_iter().for_each(|h| h.join().unwrap());
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+}
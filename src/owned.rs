@@ -0,0 +1,191 @@
+//! Handles to a [`RingBuffer`] that keep it alive through an [`Arc`], rather than borrowing it.
+
+use alloc::sync::Arc;
+use core::ops::Range;
+
+use crate::{LockError, NoUninit, Ordering, Reader, Ring, RingBuffer, SlotWriter, Start, Writer};
+
+impl<T: NoUninit, const N: usize> RingBuffer<T, N> {
+    /// Tries to acquire an [`OwnedWriteGuard`], which keeps the [`RingBuffer`] alive for as long
+    /// as it exists. Just like [`RingBuffer::try_lock`], this fails if another thread is already
+    /// holding the lock.
+    /// ```rust
+    /// # use sling::*;
+    /// # use std::sync::Arc;
+    /// let buffer: Arc<RingBuffer<[u8; 16], 1024>> = Arc::new(RingBuffer::new());
+    ///
+    /// let Ok(mut writer) = buffer.try_lock_owned() else { return };
+    ///
+    /// std::thread::spawn(move || writer.push_back([0; 16]));
+    /// ```
+    #[inline]
+    pub fn try_lock_owned(self: &Arc<Self>) -> Result<OwnedWriteGuard<T, N>, LockError> {
+        if self.try_acquire() {
+            Ok(OwnedWriteGuard {
+                buffer: Arc::clone(self),
+            })
+        } else {
+            Err(self.lock_error())
+        }
+    }
+
+    /// Creates a new [`OwnedReader`], which keeps the [`RingBuffer`] alive for as long as it
+    /// exists. It otherwise behaves just like a [`SharedReader`](crate::SharedReader).
+    /// ```rust
+    /// # use sling::*;
+    /// # use std::sync::Arc;
+    /// let buffer: Arc<RingBuffer<[u8; 16], 1024>> = Arc::new(RingBuffer::new());
+    ///
+    /// let reader = buffer.reader_owned();
+    ///
+    /// std::thread::spawn(move || reader.pop_front());
+    /// ```
+    #[inline]
+    pub fn reader_owned(self: &Arc<Self>) -> OwnedReader<T, N> {
+        Reader::new(Arc::clone(self), 0, self.version.load(Ordering::Relaxed))
+    }
+
+    /// Creates a new [`OwnedReader`] that starts reading at `start`. See
+    /// [`RingBuffer::reader_from`].
+    /// ```rust
+    /// # use sling::*;
+    /// # use std::sync::Arc;
+    /// let buffer: Arc<RingBuffer<u32, 4>> = Arc::new(RingBuffer::new());
+    ///
+    /// buffer.try_lock().unwrap().push_slice(&[1, 2, 3]);
+    ///
+    /// assert_eq!(buffer.reader_owned_from(Start::Seq(1)).pop_front(), Some(2));
+    /// ```
+    #[inline]
+    pub fn reader_owned_from(self: &Arc<Self>, start: Start) -> OwnedReader<T, N> {
+        let (index, version) = self.position(start);
+        Reader::new(Arc::clone(self), index, version)
+    }
+}
+
+/// A [`Reader`] keeping its [`RingBuffer`] alive through an [`Arc`].
+pub type OwnedReader<T, const N: usize> = Reader<T, Arc<RingBuffer<T, N>>>;
+
+/// Provides exclusive write access to a [`RingBuffer`] kept alive through an [`Arc`].
+#[derive(Debug)]
+pub struct OwnedWriteGuard<T: NoUninit, const N: usize> {
+    buffer: Arc<RingBuffer<T, N>>,
+}
+
+impl<T: NoUninit, const N: usize> OwnedWriteGuard<T, N> {
+    /// Push a new value to the back of the queue. This operation does not block. Returns the
+    /// sequence number of the message. See [`WriteGuard::push_back`](crate::WriteGuard::push_back).
+    #[inline]
+    pub fn push_back(&mut self, val: T) -> u64 {
+        self.buffer.push(val)
+    }
+
+    /// Pushes a new message to the back of the queue, letting `f` write it straight into the
+    /// [`RingBuffer`]'s slot. See [`WriteGuard::push_with`](crate::WriteGuard::push_with).
+    #[inline]
+    pub fn push_with<F: FnOnce(&mut SlotWriter<'_, T>)>(&mut self, f: F) -> u64 {
+        self.buffer.push_with(f)
+    }
+
+    /// Pushes all values to the back of the queue in order, returning the range of their
+    /// sequence numbers. See [`WriteGuard::push_slice`](crate::WriteGuard::push_slice).
+    #[inline]
+    pub fn push_slice(&mut self, vals: &[T]) -> Range<u64> {
+        self.buffer.push_all(vals.iter().copied())
+    }
+
+    /// Tags this writer for threads failing to take the lock. See
+    /// [`WriteGuard::set_tag`](crate::WriteGuard::set_tag).
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn set_tag(&mut self, tag: &'static str) {
+        self.buffer.holder.tag(tag);
+    }
+}
+
+impl<T: NoUninit, const N: usize> Extend<T> for OwnedWriteGuard<T, N> {
+    /// Pushes all values to the back of the queue. See [`OwnedWriteGuard::push_slice`].
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.buffer.push_all(iter);
+    }
+}
+
+impl<T: NoUninit, const N: usize> Drop for OwnedWriteGuard<T, N> {
+    fn drop(&mut self) {
+        self.buffer.release();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_owned_handles() {
+        let buffer = Arc::new(RingBuffer::<_, 64>::new());
+
+        let mut writer = buffer.try_lock_owned().unwrap();
+        let reader = buffer.reader_owned();
+
+        assert!(buffer.try_lock_owned().is_err());
+        assert!(buffer.try_lock().is_err());
+
+        drop(buffer);
+
+        let handle = std::thread::spawn(move || {
+            let mut read = Vec::new();
+            while read.len() < 64 {
+                if let Some(val) = reader.pop_front() {
+                    read.push(val);
+                }
+            }
+            read
+        });
+
+        std::thread::spawn(move || {
+            for i in 0..64 {
+                writer.push_back(i);
+            }
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(handle.join().unwrap(), (0..64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_owned_reader_api() {
+        let buffer = Arc::new(RingBuffer::<u32, 4>::new());
+
+        let mut writer = buffer.try_lock_owned().unwrap();
+        let reader = buffer.reader_owned();
+
+        writer.push_slice(&[0, 1, 2, 3, 4, 5]);
+
+        // An owned reader keeps count of what it missed and can iterate, just like a borrowed one.
+        let mut buf = [0; 8];
+        assert_eq!(reader.pop_into(&mut buf), 2);
+        assert_eq!(buf[..2], [4, 5]);
+        assert_eq!(reader.missed(), 4);
+
+        writer.push_slice(&[6, 7]);
+
+        assert_eq!(reader.lag(), 2);
+        assert!(reader.iter_available().eq([6, 7]));
+        assert_eq!(reader.peek_front(), Some(6));
+        assert!(reader.drain().eq([6, 7]));
+        assert!(buffer.reader_owned_from(Start::Oldest).drain().eq(4..8));
+    }
+
+    #[test]
+    fn test_owned_unlock() {
+        let buffer = Arc::new(RingBuffer::<u8, 4>::new());
+
+        let writer = buffer.try_lock_owned().unwrap();
+        drop(writer);
+
+        assert!(buffer.try_lock_owned().is_ok());
+    }
+}
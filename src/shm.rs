@@ -0,0 +1,394 @@
+//! A [`RingBuffer`](crate::RingBuffer) living in POSIX shared memory, allowing the writer and
+//! its readers to live in separate processes.
+
+use core::mem::{align_of, size_of};
+use core::ops::Range;
+use core::ptr::NonNull;
+use std::ffi::CString;
+use std::io;
+
+use crate::{
+    AtomicBool, AtomicUsize, Block, LockError, NoUninit, Ordering, Padded, Reader, Ring, Start,
+    Writer,
+};
+
+/// Identifies a mapping as one created by [`ShmRingBuffer::create`]. It is written last, so
+/// [`ShmRingBuffer::open`] never observes a partially initialized header.
+const MAGIC: usize = 0x0067_6e69_6c73;
+
+/// Incremented whenever the layout of [`Shared`] changes, so that processes built against
+/// different versions of this crate refuse to share a mapping.
+const LAYOUT_VERSION: usize = 1;
+
+/// Describes the layout of the mapping, so that [`ShmRingBuffer::open`] can reject mappings
+/// created for a different message type or capacity.
+#[repr(C)]
+struct Header {
+    magic: AtomicUsize,
+    layout_version: usize,
+    capacity: usize,
+    message_size: usize,
+    message_align: usize,
+}
+
+/// The contents of the mapping. All-zero bytes are a valid, empty buffer, which is exactly what
+/// `ftruncate` leaves us with.
+#[repr(C)]
+struct Shared<T: NoUninit, const N: usize> {
+    header: Padded<Header>,
+    locked: Padded<AtomicBool>,
+    version: Padded<AtomicUsize>,
+    index: Padded<AtomicUsize>,
+    data: [Block<T>; N],
+}
+
+/// A fixed-size, non-write-blocking, ring buffer in a `/dev/shm` mapping, that behaves like a
+/// SPMC queue and can be safely shared across processes.
+/// It follows the same protocol as [`RingBuffer`](crate::RingBuffer). As the messages are
+/// copied between processes as plain bytes, `T` must not contain pointers or references.
+///
+/// The handle returned by [`ShmRingBuffer::create`] removes the name of the mapping once it is
+/// dropped; processes that have already opened the mapping can keep using it.
+#[derive(Debug)]
+pub struct ShmRingBuffer<T: NoUninit, const N: usize> {
+    shared: NonNull<Shared<T, N>>,
+    name: CString,
+    owner: bool,
+}
+
+unsafe impl<T: NoUninit, const N: usize> Send for ShmRingBuffer<T, N> {}
+unsafe impl<T: NoUninit, const N: usize> Sync for ShmRingBuffer<T, N> {}
+
+impl<T: NoUninit, const N: usize> ShmRingBuffer<T, N> {
+    /// Creates a new, empty buffer in the shared memory object `name`, which must start with a
+    /// `/`. Fails if an object of that name already exists.
+    /// ```rust
+    /// # use sling::*;
+    /// let name = format!("/sling-doc-create-{}", std::process::id());
+    /// let buffer: ShmRingBuffer<[u8; 16], 1024> = ShmRingBuffer::create(&name).unwrap();
+    ///
+    /// let Ok(mut writer) = buffer.try_lock() else { return };
+    /// writer.push_back([0; 16]);
+    /// ```
+    pub fn create(name: &str) -> io::Result<ShmRingBuffer<T, N>> {
+        assert!(N > 0, "the capacity of a ShmRingBuffer must not be 0");
+
+        let name =
+            CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let len = size_of::<Shared<T, N>>();
+
+        // # Safety: `name` is a valid C string.
+        let fd = unsafe {
+            libc::shm_open(
+                name.as_ptr(),
+                libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+                0o600,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // # Safety: `fd` is a valid file descriptor we own.
+        let shared = unsafe {
+            if libc::ftruncate(fd, len as libc::off_t) < 0 {
+                let err = io::Error::last_os_error();
+                libc::close(fd);
+                libc::shm_unlink(name.as_ptr());
+                return Err(err);
+            }
+
+            let shared = map::<Shared<T, N>>(fd, len);
+            if shared.is_err() {
+                libc::shm_unlink(name.as_ptr());
+            }
+            shared?
+        };
+
+        // # Safety: The mapping is ours alone until we publish the magic number, and all other
+        // fields are zeroed.
+        unsafe {
+            let header = &mut *core::ptr::addr_of_mut!((*shared.as_ptr()).header.0);
+            header.layout_version = LAYOUT_VERSION;
+            header.capacity = N;
+            header.message_size = size_of::<T>();
+            header.message_align = align_of::<T>();
+            header.magic.store(MAGIC, Ordering::Release);
+        }
+
+        Ok(ShmRingBuffer {
+            shared,
+            name,
+            owner: true,
+        })
+    }
+
+    /// Opens the buffer in the shared memory object `name`, which another process created
+    /// through [`ShmRingBuffer::create`]. Fails with [`io::ErrorKind::InvalidData`] if the
+    /// object was not created for the same message type and capacity, or is not fully
+    /// initialized yet.
+    /// ```rust
+    /// # use sling::*;
+    /// let name = format!("/sling-doc-open-{}", std::process::id());
+    /// let buffer: ShmRingBuffer<u64, 1024> = ShmRingBuffer::create(&name).unwrap();
+    ///
+    /// // This would usually happen in another process.
+    /// let opened: ShmRingBuffer<u64, 1024> = ShmRingBuffer::open(&name).unwrap();
+    /// let reader = opened.reader();
+    /// ```
+    pub fn open(name: &str) -> io::Result<ShmRingBuffer<T, N>> {
+        let name =
+            CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let len = size_of::<Shared<T, N>>();
+
+        // # Safety: `name` is a valid C string.
+        let fd = unsafe { libc::shm_open(name.as_ptr(), libc::O_RDWR, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // # Safety: `fd` is a valid file descriptor we own, and `stat` is plain data.
+        let shared = unsafe {
+            let mut stat: libc::stat = core::mem::zeroed();
+            if libc::fstat(fd, &mut stat) < 0 {
+                let err = io::Error::last_os_error();
+                libc::close(fd);
+                return Err(err);
+            }
+
+            // The creator may not have resized the object yet, in which case we must not map it.
+            if stat.st_size as usize != len {
+                libc::close(fd);
+                return Err(mismatch());
+            }
+
+            map::<Shared<T, N>>(fd, len)?
+        };
+
+        let buffer = ShmRingBuffer {
+            shared,
+            name,
+            owner: false,
+        };
+
+        let header = &buffer.shared().header;
+        if header.magic.load(Ordering::Acquire) != MAGIC
+            || header.layout_version != LAYOUT_VERSION
+            || header.capacity != N
+            || header.message_size != size_of::<T>()
+            || header.message_align != align_of::<T>()
+        {
+            return Err(mismatch());
+        }
+
+        Ok(buffer)
+    }
+
+    /// Tries to acquire the [`ShmRingBuffer`]'s [`ShmWriteGuard`]. As there can only ever be one
+    /// writer across all processes, this fails if another thread or process is already holding
+    /// the lock. A process that exits while holding the lock never releases it.
+    #[inline]
+    pub fn try_lock(&self) -> Result<ShmWriteGuard<'_, T, N>, LockError> {
+        if self.try_acquire() {
+            Ok(ShmWriteGuard { buffer: self })
+        } else {
+            Err(LockError::new())
+        }
+    }
+
+    /// Creates a new [`ShmReader`] which provides shared read access of the queue. Just like
+    /// with a [`SharedReader`](crate::SharedReader), its progress is only shared by the threads
+    /// using it, so readers in other processes are not affected by it.
+    #[inline]
+    pub fn reader(&self) -> ShmReader<'_, T, N> {
+        Reader::new(self, 0, self.version().load(Ordering::Relaxed))
+    }
+
+    /// Creates a new [`ShmReader`] that starts reading at `start`. See
+    /// [`RingBuffer::reader_from`](crate::RingBuffer::reader_from).
+    #[inline]
+    pub fn reader_from(&self, start: Start) -> ShmReader<'_, T, N> {
+        let (index, version) = self.position(start);
+        Reader::new(self, index, version)
+    }
+
+    /// Reads the most recently published message without consuming anything. See
+    /// [`RingBuffer::latest`](crate::RingBuffer::latest).
+    #[inline]
+    pub fn latest(&self) -> Option<T> {
+        Ring::latest(self).map(|(_, _, (_, val))| val)
+    }
+
+    #[inline]
+    fn shared(&self) -> &Shared<T, N> {
+        // # Safety: The mapping stays valid for as long as we exist.
+        unsafe { self.shared.as_ref() }
+    }
+}
+
+/// Maps `len` bytes of the shared memory object `fd` and closes `fd`, which the mapping does not
+/// need.
+///
+/// # Safety
+///
+/// `fd` must be a valid file descriptor we own, of an object holding at least `len` bytes.
+unsafe fn map<S>(fd: libc::c_int, len: usize) -> io::Result<NonNull<S>> {
+    let ptr = libc::mmap(
+        core::ptr::null_mut(),
+        len,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_SHARED,
+        fd,
+        0,
+    );
+    let err = io::Error::last_os_error();
+    libc::close(fd);
+
+    if ptr == libc::MAP_FAILED {
+        return Err(err);
+    }
+
+    Ok(NonNull::new_unchecked(ptr.cast()))
+}
+
+fn mismatch() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "the shared memory object does not hold a matching ShmRingBuffer",
+    )
+}
+
+impl<T: NoUninit, const N: usize> Ring<T> for ShmRingBuffer<T, N> {
+    #[inline]
+    fn version(&self) -> &AtomicUsize {
+        &self.shared().version
+    }
+
+    #[inline]
+    fn index(&self) -> &AtomicUsize {
+        &self.shared().index
+    }
+
+    #[inline]
+    fn data(&self) -> &[Block<T>] {
+        &self.shared().data
+    }
+}
+
+impl<T: NoUninit, const N: usize> Writer<T> for ShmRingBuffer<T, N> {
+    #[inline]
+    fn locked(&self) -> &AtomicBool {
+        &self.shared().locked
+    }
+}
+
+impl<T: NoUninit, const N: usize> Drop for ShmRingBuffer<T, N> {
+    fn drop(&mut self) {
+        // # Safety: Nothing borrows from the mapping anymore.
+        unsafe {
+            libc::munmap(self.shared.as_ptr().cast(), size_of::<Shared<T, N>>());
+
+            if self.owner {
+                libc::shm_unlink(self.name.as_ptr());
+            }
+        }
+    }
+}
+
+/// A [`Reader`] borrowing its [`ShmRingBuffer`].
+pub type ShmReader<'read, T, const N: usize> = Reader<T, &'read ShmRingBuffer<T, N>>;
+
+/// Provides exclusive write access to the [`ShmRingBuffer`].
+#[derive(Debug)]
+pub struct ShmWriteGuard<'write, T: NoUninit, const N: usize> {
+    buffer: &'write ShmRingBuffer<T, N>,
+}
+
+impl<'write, T: NoUninit, const N: usize> ShmWriteGuard<'write, T, N> {
+    /// Push a new value to the back of the queue. This operation does not block. Returns the
+    /// sequence number of the message. See [`WriteGuard::push_back`](crate::WriteGuard::push_back).
+    #[inline]
+    pub fn push_back(&mut self, val: T) -> u64 {
+        self.buffer.push(val)
+    }
+
+    /// Pushes all values to the back of the queue in order, returning the range of their
+    /// sequence numbers. See [`WriteGuard::push_slice`](crate::WriteGuard::push_slice).
+    #[inline]
+    pub fn push_slice(&mut self, vals: &[T]) -> Range<u64> {
+        self.buffer.push_all(vals.iter().copied())
+    }
+}
+
+impl<'write, T: NoUninit, const N: usize> Extend<T> for ShmWriteGuard<'write, T, N> {
+    /// Pushes all values to the back of the queue. See [`ShmWriteGuard::push_slice`].
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.buffer.push_all(iter);
+    }
+}
+
+impl<'write, T: NoUninit, const N: usize> Drop for ShmWriteGuard<'write, T, N> {
+    fn drop(&mut self) {
+        self.buffer.release();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PopError;
+    use std::format;
+
+    #[test]
+    fn test_shm_open() {
+        let name = format!("/sling-test-open-{}", std::process::id());
+
+        let buffer = ShmRingBuffer::<u64, 16>::create(&name).unwrap();
+        let opened = ShmRingBuffer::<u64, 16>::open(&name).unwrap();
+
+        assert!(ShmRingBuffer::<u64, 16>::create(&name).is_err());
+        assert_eq!(
+            ShmRingBuffer::<u64, 32>::open(&name).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            ShmRingBuffer::<u32, 32>::open(&name).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        let mut writer = buffer.try_lock().unwrap();
+        assert!(opened.try_lock().is_err());
+
+        let reader = opened.reader();
+
+        for i in 0..20 {
+            writer.push_back(i);
+        }
+
+        assert_eq!(reader.try_pop_front(), Err(PopError::Lagged { missed: 16 }));
+        assert_eq!(reader.pop_front_with_seq(), Some((16, 16)));
+
+        drop(writer);
+        assert!(opened.try_lock().is_ok());
+    }
+
+    #[test]
+    fn test_shm_unlink() {
+        let name = format!("/sling-test-unlink-{}", std::process::id());
+
+        let buffer = ShmRingBuffer::<u64, 16>::create(&name).unwrap();
+        let opened = ShmRingBuffer::<u64, 16>::open(&name).unwrap();
+
+        drop(buffer);
+
+        assert_eq!(
+            ShmRingBuffer::<u64, 16>::open(&name).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        // The mapping outlives the name.
+        let reader = opened.reader();
+        opened.try_lock().unwrap().push_back(1);
+        assert_eq!(reader.pop_front(), Some(1));
+    }
+}
@@ -0,0 +1,157 @@
+//! Waiting for new messages asynchronously, rather than busy-polling the queue.
+
+use core::future::poll_fn;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use core::ops::Deref;
+
+use crate::{AtomicUsize, NoUninit, Reader, RingBuffer};
+
+impl<T: NoUninit, B, const N: usize> Reader<T, B>
+where
+    B: Deref<Target = RingBuffer<T, N>>,
+{
+    /// Waits for the next message and pops it from the front. Just like
+    /// [`Reader::pop_front`], this skips ahead should the writer have overrun us.
+    /// ```rust
+    /// # use sling::*;
+    /// # futures::executor::block_on(async {
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let reader = buffer.reader();
+    ///
+    /// writer.push_back(7);
+    ///
+    /// assert_eq!(reader.recv().await, 7);
+    /// # });
+    /// ```
+    pub async fn recv(&self) -> T {
+        poll_fn(|cx| {
+            self.buffer
+                .poll_pop(&self.index, &self.version, &self.missed, cx)
+        })
+        .await
+    }
+
+    /// Returns a [`Stream`] of the messages popped from the front. Threads polling streams of the
+    /// same [`Reader`] steal messages from one another, just like with
+    /// [`Reader::pop_front`].
+    pub fn stream(&self) -> ReaderStream<'_, T, N> {
+        ReaderStream::new(&self.buffer.0, &self.index, &self.version, &self.missed)
+    }
+}
+
+/// An endless [`Stream`] of the messages read by a [`Reader`]. It only yields once the writer
+/// has published a new message, and never ends.
+#[derive(Debug)]
+pub struct ReaderStream<'stream, T: NoUninit, const N: usize> {
+    buffer: &'stream RingBuffer<T, N>,
+    index: &'stream AtomicUsize,
+    version: &'stream AtomicUsize,
+    missed: &'stream AtomicUsize,
+}
+
+impl<'stream, T: NoUninit, const N: usize> ReaderStream<'stream, T, N> {
+    pub(crate) fn new(
+        buffer: &'stream RingBuffer<T, N>,
+        index: &'stream AtomicUsize,
+        version: &'stream AtomicUsize,
+        missed: &'stream AtomicUsize,
+    ) -> Self {
+        ReaderStream {
+            buffer,
+            index,
+            version,
+            missed,
+        }
+    }
+}
+
+impl<'stream, T: NoUninit, const N: usize> Stream for ReaderStream<'stream, T, N> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.buffer
+            .poll_pop(self.index, self.version, self.missed, cx)
+            .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate std;
+    use futures::StreamExt;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_recv() {
+        let buffer = RingBuffer::<_, 64>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        std::thread::scope(|s| {
+            let reader = &reader;
+            let handle = s.spawn(move || {
+                futures::executor::block_on(async {
+                    let mut read = Vec::new();
+                    for _ in 0..32 {
+                        read.push(reader.recv().await);
+                    }
+                    read
+                })
+            });
+
+            for i in 0..32 {
+                writer.push_back(i);
+                std::thread::yield_now();
+            }
+
+            assert_eq!(handle.join().unwrap(), (0..32).collect::<Vec<_>>());
+        });
+    }
+
+    #[test]
+    fn test_recv_missed() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = buffer.reader();
+
+        writer.push_slice(&[0, 1, 2, 3, 4, 5]);
+
+        futures::executor::block_on(async {
+            assert_eq!(reader.recv().await, 4);
+            assert_eq!(reader.missed(), 4);
+
+            writer.push_slice(&[6, 7, 8, 9, 10, 11, 12]);
+
+            assert_eq!(reader.stream().next().await, Some(9));
+            assert_eq!(reader.missed(), 8);
+        });
+    }
+
+    #[test]
+    fn test_stream() {
+        let buffer = std::sync::Arc::new(RingBuffer::<_, 64>::new());
+
+        let mut writer = buffer.try_lock_owned().unwrap();
+        let reader = buffer.reader_owned();
+
+        let handle = std::thread::spawn(move || {
+            futures::executor::block_on(reader.stream().take(32).collect::<Vec<_>>())
+        });
+
+        for i in 0..32 {
+            writer.push_back(i);
+            std::thread::yield_now();
+        }
+
+        assert_eq!(handle.join().unwrap(), (0..32).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,185 @@
+//! Consuming every message of a [`RingBuffer`] on a single thread, without sharing progress.
+
+use crate::{check_version, resync, NoUninit, Ordering, PopError, Ring, RingBuffer};
+
+impl<T: NoUninit, const N: usize> RingBuffer<T, N> {
+    /// Creates a new [`Subscriber`], which reads every message published after its creation.
+    /// Unlike a [`SharedReader`](crate::SharedReader), it cannot be shared between threads by
+    /// reference, so it never competes with another thread for a message.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<[u8; 16], 1024> = RingBuffer::new();
+    ///
+    /// let mut subscriber = buffer.subscriber();
+    /// ```
+    #[inline]
+    pub fn subscriber(&self) -> Subscriber<'_, T, N> {
+        Subscriber {
+            buffer: self,
+            index: 0,
+            version: self.version.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Exclusive read access to a [`RingBuffer`]. Every [`Subscriber`] reads every message on its
+/// own, and as its progress is only ever updated through `&mut self`, popping does not need to
+/// compete for messages the way a [`SharedReader`](crate::SharedReader) does.
+///
+/// Clones continue from the same position, but do not share progress with the original
+/// [`Subscriber`].
+#[derive(Debug, Clone)]
+pub struct Subscriber<'read, T: NoUninit, const N: usize> {
+    buffer: &'read RingBuffer<T, N>,
+    index: usize,
+    version: usize,
+}
+
+unsafe impl<'read, T: NoUninit, const N: usize> Send for Subscriber<'read, T, N> {}
+
+impl<'read, T: NoUninit, const N: usize> Subscriber<'read, T, N> {
+    /// Pops the next element from the front, skipping ahead should the writer have overrun us.
+    /// ```rust
+    /// # use sling::*;
+    /// let buffer: RingBuffer<u32, 16> = RingBuffer::new();
+    ///
+    /// let mut writer = buffer.try_lock().unwrap();
+    /// let mut a = buffer.subscriber();
+    /// let mut b = buffer.subscriber();
+    ///
+    /// writer.push_back(7);
+    ///
+    /// assert_eq!(a.pop_front(), Some(7));
+    /// assert_eq!(b.pop_front(), Some(7));
+    /// assert_eq!(a.pop_front(), None);
+    /// ```
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.pop_front_with_seq().map(|(_, val)| val)
+    }
+
+    /// Pops the next element from the front alongside its sequence number. See
+    /// [`SharedReader::pop_front_with_seq`](crate::SharedReader::pop_front_with_seq).
+    pub fn pop_front_with_seq(&mut self) -> Option<(u64, T)> {
+        loop {
+            match self.try_pop() {
+                Ok(message) => return Some(message),
+                Err(PopError::Lagged { .. }) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Tries to pop the next element from the front, reporting why no element could be popped
+    /// on failure. As a [`Subscriber`] is never shared, this never fails with
+    /// [`PopError::Contended`].
+    pub fn try_pop_front(&mut self) -> Result<T, PopError> {
+        self.try_pop().map(|(_, val)| val)
+    }
+
+    /// Jumps ahead to the most recently published message and pops it, discarding every
+    /// message in between. See [`SharedReader::skip_to_latest`](crate::SharedReader::skip_to_latest).
+    pub fn skip_to_latest(&mut self) -> Option<T> {
+        let (i, seq, (_, message)) = Ring::latest(self.buffer)?;
+
+        self.version = seq;
+        self.index = (i + 1) % N;
+
+        Some(message)
+    }
+
+    /// Returns the sequence the block of the next message is going to have, alongside the
+    /// block's index.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    pub(crate) fn next(&self) -> (usize, usize) {
+        // At the start of the buffer, we expect the block to be one lap ahead of us.
+        let seq = if self.index == 0 {
+            self.version.wrapping_add(2)
+        } else {
+            self.version
+        };
+
+        (seq, self.index)
+    }
+
+    pub(crate) fn try_pop(&mut self) -> Result<(u64, T), PopError> {
+        let i = self.index;
+        let seq1 = self.buffer.data[i].seq.load(Ordering::Acquire);
+        let seq1 = check_version(seq1, self.version, i)?;
+
+        // The writer has lapped us, so we move up to its lap without advancing the index.
+        if let Some(missed) = self.buffer.missed(seq1, self.version, i) {
+            self.version = resync(seq1, i);
+            return Err(PopError::Lagged { missed });
+        }
+
+        self.version = seq1;
+        self.index = (i + 1) % N;
+
+        // The writer started overwriting the block while we were reading it, so the message is
+        // lost to us.
+        self.buffer
+            .read(i, seq1)
+            .ok_or(PopError::Lagged { missed: 1 })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_subscriber_broadcast() {
+        let buffer = RingBuffer::<_, 128>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let subscriber = buffer.subscriber();
+
+        std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    let mut subscriber = subscriber.clone();
+                    s.spawn(move || {
+                        let mut read = Vec::new();
+                        while read.len() < 100 {
+                            if let Some(val) = subscriber.pop_front() {
+                                read.push(val);
+                            }
+                        }
+                        read
+                    })
+                })
+                .collect();
+
+            for i in 0..100 {
+                writer.push_back(i);
+            }
+
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), (0..100).collect::<Vec<_>>());
+            }
+        });
+    }
+
+    #[test]
+    fn test_subscriber_lagged() {
+        let buffer = RingBuffer::<_, 4>::new();
+
+        let mut writer = buffer.try_lock().unwrap();
+        let mut subscriber = buffer.subscriber();
+
+        for i in 0..6 {
+            writer.push_back(i);
+        }
+
+        assert_eq!(
+            subscriber.try_pop_front(),
+            Err(PopError::Lagged { missed: 4 })
+        );
+        assert_eq!(subscriber.pop_front_with_seq(), Some((4, 4)));
+        assert_eq!(subscriber.pop_front_with_seq(), Some((5, 5)));
+        assert_eq!(subscriber.try_pop_front(), Err(PopError::Empty));
+    }
+}
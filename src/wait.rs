@@ -0,0 +1,139 @@
+//! Letting readers wait for the writer to publish, rather than busy-polling the queue.
+
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt::Debug;
+use core::task::{Context, Poll, Waker};
+
+#[cfg(not(loom))]
+use core::sync::atomic::fence;
+#[cfg(loom)]
+use loom::sync::atomic::fence;
+
+use crate::{spin_loop, AtomicBool, AtomicUsize, NoUninit, Ordering, PopError, Ring, RingBuffer};
+
+impl<T: NoUninit, const N: usize> RingBuffer<T, N> {
+    /// Polls for the next message of the reader whose progress is tracked by `index` and
+    /// `version`, registering the task's waker with the writer should the queue be empty. The
+    /// messages the reader lost to the writer on the way are added to `missed`.
+    pub(crate) fn poll_pop(
+        &self,
+        index: &AtomicUsize,
+        version: &AtomicUsize,
+        missed: &AtomicUsize,
+        cx: &mut Context<'_>,
+    ) -> Poll<T> {
+        let mut registered = false;
+
+        loop {
+            match self.try_pop(index, version) {
+                Ok((_, val)) => return Poll::Ready(val),
+                // We need to check the queue once more after registering, as the writer may have
+                // published just before it could see our waker.
+                Err(PopError::Empty) if !registered => {
+                    self.wakers.register(cx.waker());
+                    registered = true;
+                }
+                Err(PopError::Lagged { missed: lost }) => {
+                    missed.fetch_add(lost, Ordering::Relaxed);
+                }
+                Err(PopError::Contended) => continue,
+                Err(PopError::Empty) => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Readers register their [`Waker`] here when they find the queue empty, and the writer wakes
+/// all of them after publishing the next message. As long as nobody is waiting, the writer only
+/// ever loads `waiting`.
+pub(crate) struct WaitList {
+    waiting: AtomicUsize,
+    locked: AtomicBool,
+    wakers: UnsafeCell<Vec<Waker>>,
+}
+
+unsafe impl Send for WaitList {}
+unsafe impl Sync for WaitList {}
+
+impl WaitList {
+    #[cfg(not(loom))]
+    pub(crate) const fn new() -> WaitList {
+        WaitList {
+            waiting: AtomicUsize::new(0),
+            locked: AtomicBool::new(false),
+            wakers: UnsafeCell::new(Vec::new()),
+        }
+    }
+
+    #[cfg(loom)]
+    pub(crate) fn new() -> WaitList {
+        WaitList {
+            waiting: AtomicUsize::new(0),
+            locked: AtomicBool::new(false),
+            wakers: UnsafeCell::new(Vec::new()),
+        }
+    }
+
+    /// Registers `waker` to be woken by the next call to [`WaitList::wake_all`].
+    ///
+    /// Callers must check the queue again after registering, as the writer may have published
+    /// just before the registration became visible to it.
+    pub(crate) fn register(&self, waker: &Waker) {
+        self.with_wakers(|wakers| {
+            if wakers.iter().any(|w| w.will_wake(waker)) {
+                return;
+            }
+
+            wakers.push(waker.clone());
+
+            // This is `SeqCst` so that either the writer observes our registration, or we observe
+            // its publish when checking the queue again.
+            self.waiting.fetch_add(1, Ordering::SeqCst);
+        });
+
+        fence(Ordering::SeqCst);
+    }
+
+    /// Wakes all registered wakers. This is a single load when nobody is waiting.
+    #[inline]
+    pub(crate) fn wake_all(&self) {
+        // This is `SeqCst` to pair with the `SeqCst` publish of the block, see `register`.
+        if self.waiting.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+
+        let wakers = self.with_wakers(|wakers| {
+            self.waiting.store(0, Ordering::Relaxed);
+            core::mem::take(wakers)
+        });
+
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    /// Runs `f` with exclusive access to the registered wakers.
+    fn with_wakers<R>(&self, f: impl FnOnce(&mut Vec<Waker>) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+
+        // # Safety: We are holding the lock.
+        let res = f(unsafe { &mut *self.wakers.get() });
+
+        self.locked.store(false, Ordering::Release);
+
+        res
+    }
+}
+
+impl Debug for WaitList {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WaitList")
+            .field("waiting", &self.waiting.load(Ordering::Relaxed))
+            .finish()
+    }
+}
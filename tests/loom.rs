@@ -1,40 +1,203 @@
-#![allow(dead_code)]
-use sling::RingBuffer;
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --test loom --release`.
+//!
+//! Under loom, the memory of a message is made up of loom's atomics, but it is copied in and
+//! out by the same routine as outside of loom. Only that routine's loads and stores of single
+//! cells are swapped out, so the models check how it splits and patches messages, too.
+#![cfg(loom)]
 
-const BUF_LEN: usize = 2_usize.pow(7);
-const PAYLOAD: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 0];
-const THREADS: [usize; 3] = [1, 2, 4];
-const ELEMENTS: usize = 10;
+use loom::thread;
+use sling::{Field, MultiRingBuffer, RingBuffer};
 
-fn push_pop_sling(t: usize) {
-    let queue = RingBuffer::<_, BUF_LEN>::new();
-    let mut writer = queue.try_lock().unwrap();
-    let reader = queue.reader();
+const BUF_LEN: usize = 2;
+const ELEMENTS: u64 = 3;
 
-    std::thread::scope(|s| {
-        let reader = &reader;
-        for _ in 0..t {
-            s.spawn(move || loop {
-                while reader.pop_front().is_some() {}
+/// Every message repeats its value, so that torn reads stand out.
+type Payload = [u64; 2];
 
-                std::thread::yield_now();
+/// Loom's threads need `'static` handles, so values they share are leaked for the execution.
+fn leak<T>(val: T) -> &'static T {
+    Box::leak(Box::new(val))
+}
+
+/// Frees a value leaked by `leak` once the execution is done with it. Models go through millions
+/// of executions, which would otherwise run out of memory.
+///
+/// # Safety
+///
+/// Nothing may borrow `val` anymore.
+unsafe fn free<T>(val: &'static T) {
+    drop(unsafe { Box::from_raw(val as *const T as *mut T) });
+}
+
+/// Checks that `val` was written as a whole, and that it is newer than the previous message.
+fn check(val: Payload, last: &mut u64) {
+    assert_eq!(val[0], val[1], "torn message {val:?}");
+    assert!(val[0] > *last, "message {} after {}", val[0], last);
+
+    *last = val[0];
+}
 
-                if reader.pop_front().is_none() {
-                    break;
+/// Runs `f` under loom, preempting threads at most `bound` times per execution, which keeps the
+/// models with several readers tractable. `LOOM_MAX_PREEMPTIONS` overrides the bound.
+fn model(bound: usize, f: impl Fn() + Sync + Send + 'static) {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound.get_or_insert(bound);
+    builder.check(f);
+}
+
+/// Lets each of the `readers` pop up to `pops` messages, while the writer laps the buffer.
+fn subscribers(readers: usize, pops: u64, bound: usize) {
+    model(bound, move || {
+        let buffer = leak(RingBuffer::<Payload, BUF_LEN>::new());
+        let mut writer = buffer.try_lock().unwrap();
+
+        let handles: Vec<_> = (0..readers)
+            .map(|_| {
+                let mut subscriber = buffer.subscriber();
+                thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..pops {
+                        if let Some(val) = subscriber.pop_front() {
+                            check(val, &mut last);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for i in 1..=ELEMENTS {
+            writer.push_back([i; 2]);
+        }
+
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        drop(writer);
+        unsafe { free(buffer) };
+    });
+}
+
+#[test]
+fn loom_one_reader() {
+    subscribers(1, 3, 3);
+}
+
+#[test]
+fn loom_two_readers() {
+    subscribers(2, 3, 2);
+}
+
+#[test]
+fn loom_three_readers() {
+    subscribers(3, 1, 1);
+}
+
+#[test]
+fn loom_partial_writes() {
+    model(2, || {
+        let buffer = leak(RingBuffer::<[u8; 11], BUF_LEN>::new());
+        let mut writer = buffer.try_lock().unwrap();
+        let mut subscriber = buffer.subscriber();
+
+        let handle = thread::spawn(move || {
+            let mut last = 0;
+            for _ in 0..ELEMENTS {
+                if let Some(val) = subscriber.pop_front() {
+                    assert!(val.iter().all(|&b| b == val[0]), "torn message {val:?}");
+                    assert!(val[0] > last, "message {} after {}", val[0], last);
+                    last = val[0];
                 }
+            }
+        });
+
+        // The message spans a word and some bytes, and each part patches the first word.
+        for i in 1..=ELEMENTS as u8 {
+            writer.push_with(|slot| {
+                slot.set_slice(Field::message(), 0, &[i; 5]);
+                slot.set_slice(Field::message(), 5, &[i; 6]);
             });
         }
 
-        for _ in 0..ELEMENTS {
-            writer.push_back(PAYLOAD);
-            std::thread::yield_now();
+        handle.join().unwrap();
+
+        drop(writer);
+        unsafe { free(buffer) };
+    });
+}
+
+#[test]
+fn loom_shared_reader() {
+    model(1, || {
+        let buffer = leak(RingBuffer::<Payload, BUF_LEN>::new());
+        let mut writer = buffer.try_lock().unwrap();
+        let reader = leak(buffer.reader());
+
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                thread::spawn(move || {
+                    let mut read = Vec::new();
+                    let mut last = 0;
+                    for _ in 0..ELEMENTS {
+                        if let Some(val) = reader.pop_front() {
+                            check(val, &mut last);
+                            read.push(val[0]);
+                        }
+                    }
+                    read
+                })
+            })
+            .collect();
+
+        for i in 1..=ELEMENTS {
+            writer.push_back([i; 2]);
+        }
+
+        // Threads sharing a reader steal messages from one another, so none is read twice.
+        let mut read: Vec<_> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        let len = read.len();
+        read.sort();
+        read.dedup();
+        assert_eq!(read.len(), len);
+
+        drop(writer);
+        unsafe {
+            free(reader);
+            free(buffer);
         }
     });
 }
 
-// #[test]
-fn loom() {
-    loom::model(|| {
-        THREADS.iter().for_each(|&t| push_pop_sling(t));
-    })
+#[test]
+fn loom_two_writers() {
+    model(3, || {
+        let buffer = leak(MultiRingBuffer::<Payload, BUF_LEN>::new());
+        let reader = buffer.reader();
+
+        let handles: Vec<_> = (0..2)
+            .map(|t| {
+                let writer = buffer.writer();
+                thread::spawn(move || {
+                    writer.push_back([t; 2]);
+                    writer.push_back([t + 2; 2]);
+                })
+            })
+            .collect();
+
+        // Each producer's messages are read in order, and never torn.
+        let mut last = [None; 2];
+        for _ in 0..ELEMENTS {
+            if let Some(val) = reader.pop_front() {
+                assert_eq!(val[0], val[1], "torn message {val:?}");
+                let t = (val[0] % 2) as usize;
+                assert!(last[t].is_none_or(|l| l < val[0]));
+                last[t] = Some(val[0]);
+            }
+        }
+
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        unsafe { free(buffer) };
+    });
 }